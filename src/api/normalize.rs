@@ -35,11 +35,25 @@ impl Normalize for api::User {
             .normalize()
             .unwrap_or_else(|_| format!("Corrupt User {}", self.id()));
         let slug = slugify(&name);
+        let uri = |uri: &Option<api::Uri>| uri.as_ref().map(|uri| uri.uri().clone());
         let user = User {
             id: u64_from_base36(self.id())?,
             created: *self.signup(),
             name,
             slug,
+            location: self
+                .location()
+                .as_ref()
+                .and_then(|location| location.normalize().ok()),
+            links: UserLinks {
+                twitch:        uri(self.twitch()),
+                youtube:       uri(self.youtube()),
+                twitter:       uri(self.twitter()),
+                hitbox:        uri(self.hitbox()),
+                speedrunslive: uri(self.speedrunslive()),
+            },
+            role: self.role().normalize()?,
+            name_style: Some(self.name_style().normalize()?),
         };
 
         user.validate()?;
@@ -48,6 +62,64 @@ impl Normalize for api::User {
     }
 }
 
+impl Normalize for api::UserLocation {
+    type Normalized = UserLocation;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        let region = self.region().as_ref();
+        Ok(UserLocation {
+            country_code: self.country().code().clone(),
+            country:      self.country().names().normalize()?,
+            region_code:  region.and_then(|region| region.code().clone()),
+            region:       region.and_then(|region| region.names().normalize().ok()),
+        })
+    }
+}
+
+impl Normalize for api::UserRole {
+    type Normalized = UserRole;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        Ok(match self {
+            api::UserRole::User => UserRole::User,
+            api::UserRole::Trusted => UserRole::Trusted,
+            api::UserRole::Moderator => UserRole::Moderator,
+            api::UserRole::ContentModerator => UserRole::ContentModerator,
+            api::UserRole::Admin => UserRole::Admin,
+            api::UserRole::Programmer => UserRole::Programmer,
+            api::UserRole::Banned => UserRole::Banned,
+        })
+    }
+}
+
+impl Normalize for api::UserNameStyle {
+    type Normalized = UserNameStyle;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        Ok(match self {
+            api::UserNameStyle::Solid { color } => UserNameStyle::Solid(color.normalize()?),
+            api::UserNameStyle::Gradient {
+                color_from,
+                color_to,
+            } => UserNameStyle::Gradient {
+                from: color_from.normalize()?,
+                to:   color_to.normalize()?,
+            },
+        })
+    }
+}
+
+impl Normalize for api::UserNameStyleColor {
+    type Normalized = UserColor;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        Ok(UserColor {
+            dark:  self.dark().clone(),
+            light: self.light().clone(),
+        })
+    }
+}
+
 impl Normalize for api::Names {
     type Normalized = String;
 
@@ -72,19 +144,135 @@ impl Normalize for api::Names {
 }
 
 impl Normalize for api::Game {
-    type Normalized = (Game, Vec<Category>, Vec<Level>);
+    type Normalized = (
+        Game,
+        Vec<Category>,
+        Vec<Level>,
+        Vec<Variable>,
+        Vec<Platform>,
+        Vec<Region>,
+        Vec<GameTag>,
+        Option<GameRelation>,
+    );
 
     fn normalize(&self) -> Result<Self::Normalized, Error> {
+        let platforms = self
+            .platforms()
+            .iter()
+            .map(Normalize::normalize)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut moderators = self
+            .moderators()
+            .iter()
+            .map(|(user_id, kind)| -> Result<GameModerator, Error> {
+                Ok(GameModerator {
+                    user_id: u64_from_base36(user_id)?,
+                    kind:    match kind {
+                        api::GameModeratorType::Moderator => ModeratorKind::Moderator,
+                        api::GameModeratorType::SuperModerator =>
+                            ModeratorKind::SuperModerator,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // the API gives moderators as a map, so they're in no particular order
+        moderators.sort();
+        let regions = self
+            .regions()
+            .iter()
+            .map(Normalize::normalize)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut tags = Vec::new();
+        for genre in self.genres().iter() {
+            tags.push(game_tag(GameTagKind::Genre, genre.id(), genre.name())?);
+        }
+        for developer in self.developers().iter() {
+            tags.push(game_tag(
+                GameTagKind::Developer,
+                developer.id(),
+                developer.name(),
+            )?);
+        }
+        for publisher in self.publishers().iter() {
+            tags.push(game_tag(
+                GameTagKind::Publisher,
+                publisher.id(),
+                publisher.name(),
+            )?);
+        }
+        for engine in self.engines().iter() {
+            tags.push(game_tag(GameTagKind::Engine, engine.id(), engine.name())?);
+        }
+        for gametype in self.gametypes().iter() {
+            tags.push(game_tag(
+                GameTagKind::GameType,
+                gametype.id(),
+                gametype.name(),
+            )?);
+        }
+
+        // the first of the given asset keys that's present, so we can prefer
+        // the largest size of cover that's available
+        let asset = |keys: &[&str]| {
+            keys.iter()
+                .filter_map(|key| self.assets().get(*key).cloned().flatten())
+                .next()
+                .map(|asset| GameAsset {
+                    uri:    asset.uri().clone(),
+                    width:  *asset.width(),
+                    height: *asset.height(),
+                })
+        };
+
         let game = Game {
-            id:             u64_from_base36(self.id())?,
-            name:           self.names().normalize()?,
-            slug:           slugify(self.abbreviation()),
-            src_slug:       self.abbreviation().to_string(),
-            created:        *self.created(),
+            id: u64_from_base36(self.id())?,
+            name: self.names().normalize()?,
+            slug: slugify(self.abbreviation()),
+            src_slug: self.abbreviation().to_string(),
+            created: *self.created(),
             primary_timing: self.ruleset().default_time().normalize()?,
+            platform_ids: platforms.iter().map(|platform| platform.id).collect(),
+            region_ids: regions.iter().map(|region| region.id).collect(),
+            release_date: Some(*self.release_date()),
+            romhack: *self.romhack(),
+            tag_ids: tags.iter().map(|tag| tag.id).collect(),
+            assets: GameAssets {
+                cover:      asset(&[
+                    "cover-large",
+                    "cover-medium",
+                    "cover-small",
+                    "cover-tiny",
+                ]),
+                icon:       asset(&["icon"]),
+                logo:       asset(&["logo"]),
+                background: asset(&["background"]),
+            },
+            moderators,
         };
         game.validate()?;
 
+        // speedrun.com only links to the base game and series; derived games
+        // and ROM hacks are found by following those links backwards.
+        let mut base_game_id = None;
+        let mut series_ids = Vec::new();
+        for link in self.links() {
+            match link {
+                api::Link::BaseGame(uri) => base_game_id = Some(id_from_uri(uri)?),
+                api::Link::Series(uri) => series_ids.push(id_from_uri(uri)?),
+                _ => {}
+            }
+        }
+        let relation = if base_game_id.is_some() || !series_ids.is_empty() {
+            Some(GameRelation {
+                game_id: game.id,
+                base_game_id,
+                series_ids,
+            })
+        } else {
+            None
+        };
+
         let categories = self
             .categories()
             .iter()
@@ -122,40 +310,285 @@ impl Normalize for api::Game {
             })
             .collect::<Result<_, _>>()?;
 
-        Ok((game, categories, levels))
+        let variables = self
+            .variables()
+            .iter()
+            .map(|api_variable| -> Result<Variable, Error> {
+                let mut values = api_variable
+                    .values()
+                    .values()
+                    .iter()
+                    .map(|(id, api_value)| -> Result<VariableValue, Error> {
+                        Ok(VariableValue {
+                            id:            u64_from_base36(id)?,
+                            slug:          slugify(api_value.label()),
+                            label:         api_value.label().to_string(),
+                            rules:         api_value.rules().clone().unwrap_or_default(),
+                            miscellaneous: api_value
+                                .flags()
+                                .as_ref()
+                                .and_then(|flags| *flags.miscellaneous())
+                                .unwrap_or(false),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                values.sort_by(|a, b| (&a.label, a.id).cmp(&(&b.label, b.id)));
+
+                let variable = Variable {
+                    game_id: u64_from_base36(self.id())?,
+                    id: u64_from_base36(api_variable.id())?,
+                    category_id: match api_variable.category() {
+                        None => None,
+                        Some(category_id) => Some(u64_from_base36(category_id)?),
+                    },
+                    scope: api_variable.scope().normalize()?,
+                    slug: slugify(api_variable.name()),
+                    name: api_variable.name().to_string(),
+                    is_subcategory: *api_variable.is_subcategory(),
+                    mandatory: *api_variable.mandatory(),
+                    obsoletes: *api_variable.obsoletes(),
+                    default_value_id: match api_variable.values().default() {
+                        None => None,
+                        Some(value_id) => Some(u64_from_base36(value_id)?),
+                    },
+                    values,
+                };
+
+                variable.validate()?;
+
+                Ok(variable)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((
+            game, categories, levels, variables, platforms, regions, tags, relation,
+        ))
     }
 }
 
+/// Parses the ID at the end of an API URI, such as
+/// `https://www.speedrun.com/api/v1/series/rv7emz49`.
+fn id_from_uri(uri: &str) -> Result<u64, Error> {
+    let id = uri.rsplit('/').next().unwrap_or_default();
+    Ok(u64_from_base36(id)?)
+}
+
+fn game_tag(kind: GameTagKind, id: &str, name: &str) -> Result<GameTag, Error> {
+    let tag = GameTag {
+        id: u64_from_base36(id)?,
+        kind,
+        slug: slugify(name),
+        name: name.to_string(),
+    };
+
+    tag.validate()?;
+
+    Ok(tag)
+}
+
+impl Normalize for api::Platform {
+    type Normalized = Platform;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        let platform = Platform {
+            id:       u64_from_base36(self.id())?,
+            slug:     slugify(self.name()),
+            name:     self.name().to_string(),
+            released: *self.released(),
+        };
+
+        platform.validate()?;
+
+        Ok(platform)
+    }
+}
+
+impl Normalize for api::Region {
+    type Normalized = Region;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        let region = Region {
+            id:   u64_from_base36(self.id())?,
+            slug: slugify(self.name()),
+            name: self.name().to_string(),
+        };
+
+        region.validate()?;
+
+        Ok(region)
+    }
+}
+
+/// A normalized run, which is kept apart from verified runs if it's pending or
+/// rejected.
+#[derive(Debug, Clone, From)]
+pub enum NormalizedRun {
+    Verified(Run),
+    Unverified(UnverifiedRun),
+}
+
 impl Normalize for api::Run {
-    // Option because we drop runs that aren't verified.
-    type Normalized = Option<Run>;
-
-    fn normalize(&self) -> Result<Self::Normalized, Error> {
-        match self.status() {
-            api::RunStatus::Verified { .. } => {
-                let run = Run {
-                    game_id:     u64_from_base36(self.game())?,
-                    id:          u64_from_base36(self.id())?,
-                    created:     *self.submitted(),
-                    date:        *self.date(),
-                    category_id: u64_from_base36(self.category())?,
-                    level_id:    match self.level() {
-                        None => None,
-                        Some(level_id) => Some(u64_from_base36(level_id)?),
-                    },
-                    times_ms:    self.times().normalize()?,
-                    players:     self
-                        .players()
-                        .iter()
-                        .map(Normalize::normalize)
-                        .map(Result::unwrap)
-                        .collect(),
-                };
-                run.validate()?;
-                Ok(Some(run))
-            }
-            _ => Ok(None),
+    type Normalized = NormalizedRun;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        let (examiner, verified) = match self.status() {
+            api::RunStatus::New => (None, None),
+            api::RunStatus::Rejected { examiner, .. } => (examiner.as_ref(), None),
+            api::RunStatus::Verified {
+                examiner,
+                verify_date,
+            } => (examiner.as_ref(), *verify_date),
+        };
+
+        let run = Run {
+            game_id: u64_from_base36(self.game())?,
+            id: u64_from_base36(self.id())?,
+            created: *self.submitted(),
+            date: *self.date(),
+            category_id: u64_from_base36(self.category())?,
+            level_id: match self.level() {
+                None => None,
+                Some(level_id) => Some(u64_from_base36(level_id)?),
+            },
+            times_ms: self.times().normalize()?,
+            players: self
+                .players()
+                .iter()
+                .map(Normalize::normalize)
+                .map(Result::unwrap)
+                .collect(),
+            values: self
+                .values()
+                .iter()
+                .map(|(variable_id, value_id)| -> Result<_, Error> {
+                    Ok((u64_from_base36(variable_id)?, u64_from_base36(value_id)?))
+                })
+                .collect::<Result<_, _>>()?,
+            videos: match self.videos() {
+                Some(videos) => videos.normalize()?,
+                None => vec![],
+            },
+            comment: self
+                .comment()
+                .clone()
+                .filter(|comment| !comment.trim().is_empty()),
+            platform_id: match self.system().platform() {
+                None => None,
+                Some(platform_id) => Some(u64_from_base36(platform_id)?),
+            },
+            region_id: match self.system().region() {
+                None => None,
+                Some(region_id) => Some(u64_from_base36(region_id)?),
+            },
+            emulated: *self.system().emulated(),
+            examiner_id: match examiner {
+                None => None,
+                Some(examiner_id) => Some(u64_from_base36(examiner_id)?),
+            },
+            verified,
+        };
+
+        let normalized = match self.status() {
+            api::RunStatus::Verified { .. } => NormalizedRun::Verified(run),
+            api::RunStatus::New => NormalizedRun::Unverified(UnverifiedRun {
+                run,
+                status: ModerationStatus::Pending,
+                reason: None,
+            }),
+            api::RunStatus::Rejected { reason, .. } =>
+                NormalizedRun::Unverified(UnverifiedRun {
+                    run,
+                    status: ModerationStatus::Rejected,
+                    reason: reason.clone().filter(|reason| !reason.trim().is_empty()),
+                }),
+        };
+
+        match &normalized {
+            NormalizedRun::Verified(run) => run.validate()?,
+            NormalizedRun::Unverified(unverified) => unverified.validate()?,
         }
+
+        Ok(normalized)
+    }
+}
+
+impl Normalize for api::RunVideos {
+    type Normalized = Vec<RunVideo>;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        // Older runs only have free text, which is usually just a URL.
+        let urls: Vec<&str> = match (self.links(), self.text()) {
+            (Some(links), _) => links.iter().map(|link| link.uri().as_str()).collect(),
+            (None, Some(text)) if text.trim().starts_with("http") => vec![text.trim()],
+            (None, _) => vec![],
+        };
+
+        Ok(urls.into_iter().map(parse_video_url).collect())
+    }
+}
+
+/// Identifies the platform, video ID, and start time of a video from its URL.
+fn parse_video_url(url: &str) -> RunVideo {
+    lazy_static! {
+        static ref YOUTUBE: Regex = Regex::new(
+            r"(?xi)
+            ^(?:https?://)?
+            (?:(?:www|m)\.)?
+            (?:
+                youtube\.com/(?:watch\?(?:\S*&)?v=|embed/|v/)
+                |
+                youtu\.be/
+            )
+            ([\w-]{11})
+        "
+        )
+        .unwrap();
+        static ref TWITCH_VOD: Regex = Regex::new(
+            r"(?xi)
+            ^(?:https?://)?
+            (?:(?:www|m|go)\.)?
+            twitch\.tv/
+            (?:videos/|\w+/v/)
+            (\d+)
+        "
+        )
+        .unwrap();
+        static ref START: Regex = Regex::new(r"[?&#](?:t|start|time)=([0-9hms]+)").unwrap();
+        static ref TIMESTAMP: Regex =
+            Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s?)?$").unwrap();
+    }
+
+    let url = url.trim();
+
+    let (platform, video_id) = if let Some(captures) = YOUTUBE.captures(url) {
+        (VideoPlatform::YouTube, Some(captures[1].to_string()))
+    } else if let Some(captures) = TWITCH_VOD.captures(url) {
+        (VideoPlatform::TwitchVod, Some(captures[1].to_string()))
+    } else {
+        (VideoPlatform::Other, None)
+    };
+
+    let start_s = match platform {
+        VideoPlatform::Other => None,
+        _ => START.captures(url).and_then(|captures| {
+            // a start time too large to fit is treated as missing
+            let timestamp = TIMESTAMP.captures(&captures[1])?;
+            let hours = checked_u64_or_zero(timestamp.get(1))?;
+            let minutes = checked_u64_or_zero(timestamp.get(2))?;
+            let seconds = checked_u64_or_zero(timestamp.get(3))?;
+            hours
+                .checked_mul(60)?
+                .checked_add(minutes)?
+                .checked_mul(60)?
+                .checked_add(seconds)
+        }),
+    };
+
+    RunVideo {
+        url: url.to_string(),
+        platform,
+        video_id,
+        start_s,
     }
 }
 
@@ -170,6 +603,20 @@ impl Normalize for api::RunPlayer {
     }
 }
 
+impl Normalize for api::VariableScope {
+    type Normalized = VariableScope;
+
+    fn normalize(&self) -> Result<Self::Normalized, Error> {
+        Ok(match self {
+            api::VariableScope::Global => VariableScope::Global,
+            api::VariableScope::FullGame => VariableScope::FullGame,
+            api::VariableScope::AllLevels => VariableScope::AllLevels,
+            api::VariableScope::SingleLevel { level } =>
+                VariableScope::SingleLevel(u64_from_base36(level)?),
+        })
+    }
+}
+
 impl Normalize for api::CategoryType {
     type Normalized = CategoryType;
 
@@ -197,20 +644,6 @@ impl Normalize for api::RunTimes {
     type Normalized = RunTimesMs;
 
     fn normalize(&self) -> Result<Self::Normalized, Error> {
-        fn u64_or_zero(s: Option<regex::Match<'_>>) -> u64 {
-            match s {
-                Some(s) => {
-                    let s = s.as_str();
-                    if s.is_empty() {
-                        0
-                    } else {
-                        s.parse().unwrap()
-                    }
-                }
-                None => 0,
-            }
-        }
-
         fn parse_duration_ms(s: &str) -> u64 {
             lazy_static! {
                 static ref RE: Regex = Regex::new(
@@ -250,3 +683,69 @@ impl Normalize for api::RunTimes {
         })
     }
 }
+
+fn u64_or_zero(s: Option<regex::Match<'_>>) -> u64 {
+    match s {
+        Some(s) => {
+            let s = s.as_str();
+            if s.is_empty() {
+                0
+            } else {
+                s.parse().unwrap()
+            }
+        }
+        None => 0,
+    }
+}
+
+/// Like `u64_or_zero`, but `None` instead of panicking if the number doesn't fit.
+fn checked_u64_or_zero(s: Option<regex::Match<'_>>) -> Option<u64> {
+    match s {
+        Some(s) if !s.as_str().is_empty() => s.as_str().parse().ok(),
+        _ => Some(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_url() {
+        let video = parse_video_url(
+            " https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=1m30s ",
+        );
+        assert_eq!(
+            video.url,
+            "https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=1m30s"
+        );
+        assert_eq!(video.platform, VideoPlatform::YouTube);
+        assert_eq!(video.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(video.start_s, Some(90));
+
+        let video = parse_video_url("https://youtu.be/dQw4w9WgXcQ");
+        assert_eq!(video.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(video.start_s, None);
+
+        let video = parse_video_url("https://www.twitch.tv/videos/123456789?t=01h02m03s");
+        assert_eq!(video.platform, VideoPlatform::TwitchVod);
+        assert_eq!(video.video_id.as_deref(), Some("123456789"));
+        assert_eq!(video.start_s, Some(3723));
+
+        let video = parse_video_url("https://www.twitch.tv/runner/v/4567?t=42");
+        assert_eq!(video.video_id.as_deref(), Some("4567"));
+        assert_eq!(video.start_s, Some(42));
+
+        let video = parse_video_url("https://youtu.be/dQw4w9WgXcQ?t=99999999999999999999s");
+        assert_eq!(video.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(video.start_s, None);
+
+        let video = parse_video_url("https://youtu.be/dQw4w9WgXcQ?t=9999999999999999h");
+        assert_eq!(video.start_s, None);
+
+        let video = parse_video_url("https://example.com/run.mp4?t=42");
+        assert_eq!(video.platform, VideoPlatform::Other);
+        assert_eq!(video.video_id, None);
+        assert_eq!(video.start_s, None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Print a changelog of the differences between two table snapshots.
+#![allow(missing_docs, clippy::useless_attribute)]
+
+use std::{
+    fs::File,
+    io::{prelude::*, BufWriter},
+    sync::Arc,
+};
+
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+
+use speedruns::data::{database::Tables, diff::diff};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::try_init_from_env(
+        env_logger::Env::new()
+            .default_filter_or(format!("{}=info,speedruns=info", module_path!())),
+    )?;
+
+    let args = clap::App::new("diff")
+        .about("Prints a JSON changelog of the differences between two table snapshots.")
+        .arg(
+            clap::Arg::with_name("before")
+                .value_name("BEFORE")
+                .default_value("data/normalized/tables.previous.bin")
+                .help("The older snapshot."),
+        )
+        .arg(
+            clap::Arg::with_name("after")
+                .value_name("AFTER")
+                .default_value("data/normalized/tables.bin")
+                .help("The newer snapshot."),
+        )
+        .arg(
+            clap::Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("PATH")
+                .help("Writes the changelog to a file instead of standard output."),
+        )
+        .get_matches();
+
+    let before_path = args.value_of("before").expect("has default");
+    let after_path = args.value_of("after").expect("has default");
+
+    info!("Loading {}...", before_path);
+    let before = Tables::load_snapshot(before_path)?;
+    info!("Loading {}...", after_path);
+    let after = Tables::load_snapshot(after_path)?;
+
+    info!("Comparing...");
+    let changelog = diff(Arc::new(before), Arc::new(after))?;
+    info!("{}", changelog);
+
+    match args.value_of("output") {
+        Some(path) => {
+            let mut buffer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(&mut buffer, &changelog)?;
+            buffer.write_all(b"\n")?;
+        }
+        None => {
+            let stdout = std::io::stdout();
+            let mut buffer = BufWriter::new(stdout.lock());
+            serde_json::to_writer_pretty(&mut buffer, &changelog)?;
+            buffer.write_all(b"\n")?;
+        }
+    }
+
+    Ok(())
+}
@@ -6,7 +6,6 @@
 )]
 
 use std::{
-    collections::HashSet,
     fs::File,
     io::{prelude::*, BufReader, BufWriter},
 };
@@ -19,17 +18,13 @@ use serde_json::{Deserializer as JsonDeserializer, Value as JsonValue};
 use tempfile::NamedTempFile;
 
 use speedruns::{
-    api::{self, normalize::Normalize},
-    data::{
-        database::{Database, IntegrityError, Tables},
-        models::{AnyModel, AnyModelVec},
+    api::{
+        self,
+        normalize::{Normalize, NormalizedRun},
     },
+    data::{database::Tables, migrations::Manifest, repair::RepairPolicy},
 };
 
-// TODO: include Run::videos()
-// TODO: include Run::comment()
-// TODO: include Game::variables() and Run::values()
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::try_init_from_env(
         env_logger::Env::new()
@@ -37,15 +32,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
 
     let mut runs = Vec::new();
+    let mut unverified_runs = Vec::new();
     let mut users = Vec::new();
     let mut games = Vec::new();
     let mut categories = Vec::new();
     let mut levels = Vec::new();
+    let mut variables = Vec::new();
+    // Platforms, regions, and tags are shared between games, so these will
+    // have duplicates, which are merged when they're inserted into the tables.
+    // Relations to games we don't keep are removed by the repair below.
+    let mut platforms = Vec::new();
+    let mut regions = Vec::new();
+    let mut game_tags = Vec::new();
+    let mut game_relations = Vec::new();
 
     info!("Loading API runs...");
     for api_run in load_api_type::<api::Run>("data/api/runs.jsonl.gz")? {
-        if let Some(run) = api_run.normalize().unwrap() {
-            runs.push(run);
+        match api_run.normalize().unwrap() {
+            NormalizedRun::Verified(run) => runs.push(run),
+            NormalizedRun::Unverified(run) => unverified_runs.push(run),
         }
     }
 
@@ -83,9 +88,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    info!("Loading API games, with categories and levels...");
+    info!(
+        "Loading API games, with categories, levels, variables, platforms, regions, and \
+         tags..."
+    );
     for api_game in load_api_type::<api::Game>("data/api/games.jsonl.gz")? {
-        let (game, mut game_categories, mut game_levels) = api_game.normalize().unwrap();
+        let (
+            game,
+            mut game_categories,
+            mut game_levels,
+            mut game_variables,
+            mut game_platforms,
+            mut game_regions,
+            mut tags,
+            relation,
+        ) = api_game.normalize().unwrap();
 
         if std::env::var("SPEEDRUNS_ALL") == Ok("ALL".to_string())
             || [
@@ -103,171 +120,83 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             games.push(game);
             categories.append(&mut game_categories);
             levels.append(&mut game_levels);
+            variables.append(&mut game_variables);
+            platforms.append(&mut game_platforms);
+            regions.append(&mut game_regions);
+            game_tags.append(&mut tags);
+            game_relations.extend(relation);
         }
     }
 
     info!("Validating and cleaning API data...");
-
-    loop {
-        // memory leak, so hopefully not many iterations!
-        match Database::new(Box::leak(Box::new(Tables::new(
-            runs.clone(),
-            users.clone(),
-            games.clone(),
-            categories.clone(),
-            levels.clone(),
-        )))) {
-            Ok(_) => {
-                info!("Database validation successful.");
-                break
-            }
-            Err(errors) => {
-                error!("Database validation failed: {}", errors);
-                let mut dead_run_ids = HashSet::<u64>::new();
-                let mut dead_game_ids = HashSet::<u64>::new();
-                let mut dead_user_ids = HashSet::<u64>::new();
-                let mut dead_level_ids = HashSet::<u64>::new();
-                let mut dead_category_ids = HashSet::<u64>::new();
-
-                for error in errors.errors {
-                    match error {
-                        IntegrityError::IndexingError => {
-                            error!("indexing failed");
-                        }
-                        IntegrityError::ForeignKeyMissing { source, .. } => {
-                            use AnyModel::*;
-                            match source {
-                                Run(run) => dead_run_ids.insert(*run.id()),
-                                User(user) => dead_user_ids.insert(*user.id()),
-                                Game(game) => dead_game_ids.insert(*game.id()),
-                                Level(level) => dead_level_ids.insert(*level.id()),
-                                Category(category) =>
-                                    dead_category_ids.insert(*category.id()),
-                            };
-                        }
-                        IntegrityError::CheckFailed { .. } => {
-                            panic!("value incorrectly normalized and fails validation?!");
-                        }
-                        IntegrityError::NonUniqueSlug { sources, .. } => {
-                            use AnyModelVec::*;
-                            match sources {
-                                Runs(_) => unreachable!("runs don't have slugs?!"),
-                                Games(games) => {
-                                    let dead_dupes = games
-                                        .iter()
-                                        .sorted_by_key(|game| {
-                                            (
-                                                game.created(),
-                                                game.slug().len(),
-                                                game.name().len(),
-                                                game.name(),
-                                                game.id(),
-                                            )
-                                                .clone()
-                                        })
-                                        .skip(1);
-                                    for dupe in dead_dupes {
-                                        dead_game_ids.insert(*dupe.id());
-                                    }
-                                }
-                                Users(users) => {
-                                    let dead_dupes = users
-                                        .iter()
-                                        .sorted_by_key(|user| {
-                                            (
-                                                user.created(),
-                                                user.name().len(),
-                                                user.name(),
-                                                user.id(),
-                                            )
-                                                .clone()
-                                        })
-                                        .skip(1);
-                                    for dupe in dead_dupes {
-                                        dead_user_ids.insert(*dupe.id());
-                                    }
-                                }
-                                Categories(categories) => {
-                                    let dead_dupes = categories
-                                        .iter()
-                                        .sorted_by_key(|category| {
-                                            (
-                                                category.name().len(),
-                                                category.name(),
-                                                category.id(),
-                                            )
-                                                .clone()
-                                        })
-                                        .skip(1);
-                                    for dupe in dead_dupes {
-                                        dead_category_ids.insert(*dupe.id());
-                                    }
-                                }
-                                Levels(levels) => {
-                                    let dead_dupes = levels
-                                        .iter()
-                                        .sorted_by_key(|level| {
-                                            (level.name().len(), level.name(), level.id())
-                                                .clone()
-                                        })
-                                        .skip(1);
-                                    for dupe in dead_dupes {
-                                        dead_level_ids.insert(*dupe.id());
-                                    }
-                                }
-                            };
-                        }
-                        IntegrityError::MissingPrimaryTiming(run) => {
-                            dead_run_ids.insert(*run.id());
-                        }
-                    }
-                }
-
-                error!(
-                    "{:6} ({:3}%) invalid runs",
-                    dead_run_ids.len(),
-                    (dead_run_ids.len() * 100) / runs.len().max(1)
-                );
-                error!(
-                    "{:6} ({:3}%) invalid users",
-                    dead_user_ids.len(),
-                    (dead_user_ids.len() * 100) / users.len().max(1)
-                );
-                error!(
-                    "{:6} ({:3}%) invalid games",
-                    dead_game_ids.len(),
-                    (dead_game_ids.len() * 100) / games.len().max(1)
-                );
-                error!(
-                    "{:6} ({:3}%) invalid categories",
-                    dead_category_ids.len(),
-                    (dead_category_ids.len() * 100) / categories.len().max(1)
-                );
-                error!(
-                    "{:6} ({:3}%) invalid levels",
-                    dead_level_ids.len(),
-                    (dead_level_ids.len() * 100) / levels.len().max(1)
-                );
-
-                runs.retain(|x| !dead_run_ids.contains(x.id()));
-                users.retain(|x| !dead_user_ids.contains(x.id()));
-                games.retain(|x| !dead_game_ids.contains(x.id()));
-                categories.retain(|x| !dead_category_ids.contains(x.id()));
-                levels.retain(|x| !dead_level_ids.contains(x.id()));
-            }
-        }
+    let (tables, report) = Tables::new(
+        runs,
+        users,
+        games,
+        categories,
+        levels,
+        variables,
+        platforms,
+        regions,
+        game_tags,
+        game_relations,
+        unverified_runs,
+    )
+    .repair(&RepairPolicy::default());
+    if report.valid {
+        info!("Database validation successful. {}", report);
+    } else {
+        error!("Database validation failed. {}", report);
+        return Err("normalized data still invalid after repairs".into())
     }
 
-    info!("Dumping {} games...", games.len());
-    dump_table("data/normalized/games", games)?;
-    info!("Dumping {} users...", users.len());
-    dump_table("data/normalized/users", users)?;
-    info!("Dumping {} runs...", runs.len());
-    dump_table("data/normalized/runs", runs)?;
-    info!("Dumping {} categories...", categories.len());
-    dump_table("data/normalized/categories", categories)?;
-    info!("Dumping {} levels...", levels.len());
-    dump_table("data/normalized/levels", levels)?;
+    info!("Dumping {} games...", tables.games().len());
+    dump_table("data/normalized/games", tables.games().values())?;
+    info!("Dumping {} users...", tables.users().len());
+    dump_table("data/normalized/users", tables.users().values())?;
+    info!("Dumping {} runs...", tables.runs().len());
+    dump_table("data/normalized/runs", tables.runs().values())?;
+    info!("Dumping {} categories...", tables.categories().len());
+    dump_table("data/normalized/categories", tables.categories().values())?;
+    info!("Dumping {} levels...", tables.levels().len());
+    dump_table("data/normalized/levels", tables.levels().values())?;
+    info!("Dumping {} variables...", tables.variables().len());
+    dump_table("data/normalized/variables", tables.variables().values())?;
+    info!("Dumping {} platforms...", tables.platforms().len());
+    dump_table("data/normalized/platforms", tables.platforms().values())?;
+    info!("Dumping {} regions...", tables.regions().len());
+    dump_table("data/normalized/regions", tables.regions().values())?;
+    info!("Dumping {} game tags...", tables.game_tags().len());
+    dump_table("data/normalized/game_tags", tables.game_tags().values())?;
+    info!(
+        "Dumping {} game relations...",
+        tables.game_relations().len()
+    );
+    dump_table(
+        "data/normalized/game_relations",
+        tables.game_relations().values(),
+    )?;
+    info!(
+        "Dumping {} unverified runs...",
+        tables.unverified_runs().len()
+    );
+    dump_table(
+        "data/normalized/unverified_runs",
+        tables.unverified_runs().values(),
+    )?;
+    Manifest::current().write("data/normalized")?;
+
+    if std::path::Path::new("data/normalized/tables.bin").exists() {
+        info!("Keeping previous binary snapshot for diffing...");
+        std::fs::rename(
+            "data/normalized/tables.bin",
+            "data/normalized/tables.previous.bin",
+        )?;
+    }
+    info!("Writing binary snapshot...");
+    tables.write_snapshot("data/normalized/tables.bin")?;
+    info!("Writing per-game shards...");
+    tables.write_shards("data/normalized/shards")?;
 
     Ok(())
 }
@@ -287,14 +216,14 @@ fn load_api_type<ApiType: DeserializeOwned>(
         .collect())
 }
 
-fn dump_table<T: Serialize + Ord>(
+fn dump_table<'a, T: 'a + Serialize + Ord>(
     path: &str,
-    table: Vec<T>,
+    table: impl Iterator<Item = &'a T>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut file = NamedTempFile::new_in("data")?;
     {
         let mut buffer = BufWriter::new(&mut file);
-        for data in table.iter().sorted() {
+        for data in table.sorted() {
             serde_json::to_writer(&mut buffer, &data)?;
             buffer.write_all(b"\n")?;
         }
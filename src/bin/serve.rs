@@ -7,7 +7,11 @@
 )]
 #![deny(unconditional_recursion)]
 
-use std::{fs::File, io::BufReader, sync::Arc};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
 
 use actix_cors::{self};
 use actix_web::{self, web};
@@ -16,12 +20,11 @@ use juniper::{self, http::GraphQLRequest};
 use lazy_static::lazy_static;
 #[allow(unused)] use log::{debug, error, info, trace, warn};
 
-use serde::de::DeserializeOwned;
-use serde_json::{Deserializer as JsonDeserializer, Value as JsonValue};
-
 use speedruns::data::{
     database::{Database, Tables},
     graphql,
+    jsonl::LoadMode,
+    shards::ShardedDatabase,
 };
 
 async fn graphiql() -> actix_web::HttpResponse {
@@ -38,20 +41,174 @@ async fn playground() -> actix_web::HttpResponse {
         .body(html)
 }
 
+/// How often we check the normalized data directory for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(16);
+
 lazy_static! {
-    static ref DATABASE: Arc<Database> = {
-        let tables: &'static Tables = Box::leak(Box::new(unpack_bundled_tables()));
-        Database::new(tables).expect("database should be valid")
+    /// The currently-serving database. Requests clone the inner [Arc] so that
+    /// they can finish against the same snapshot even if it's replaced.
+    static ref DATABASE: RwLock<graphql::Context> =
+        RwLock::new(load_database().unwrap_or_else(|error| {
+            panic!("Refusing to start, failed to load database: {}", error)
+        }));
+    /// Held while a replacement database is being built, so that concurrent
+    /// reload triggers don't build several at once.
+    static ref RELOADING: Mutex<()> = Mutex::new(());
+}
+
+/// Returns the currently-serving database.
+fn current_database() -> graphql::Context {
+    DATABASE.read().expect("database lock poisoned").clone()
+}
+
+/// Loads and validates a new database from the normalized data.
+///
+/// If `SPEEDRUNS_SHARDS` is set, only the index of the sharded data is loaded,
+/// and up to that many games' shards are loaded as they're used.
+fn load_database() -> Result<graphql::Context, String> {
+    let context = match std::env::var("SPEEDRUNS_SHARDS") {
+        Ok(capacity) => {
+            let capacity = capacity
+                .parse()
+                .map_err(|_| "SPEEDRUNS_SHARDS must be a number".to_string())?;
+            info!("Loading sharded database index...");
+            let shards = ShardedDatabase::open("data/normalized/shards", capacity)
+                .map_err(|error| error.to_string())?;
+            graphql::Context::sharded(Arc::new(shards))
+        }
+        Err(_) => {
+            let tables = unpack_bundled_tables().map_err(|error| error.to_string())?;
+            let database =
+                Database::new(Arc::new(tables)).map_err(|errors| errors.to_string())?;
+            graphql::Context::new(database)
+        }
+    };
+    context.database.search_index();
+    Ok(context)
+}
+
+/// Builds and validates a new database and, only if that succeeds, swaps it in
+/// for the currently-serving one.
+fn reload_database() -> Result<(), String> {
+    let _reloading = RELOADING.lock().expect("reload lock poisoned");
+
+    info!("Reloading database...");
+    let database = match catch_unwind(AssertUnwindSafe(load_database)) {
+        Ok(Ok(database)) => database,
+        Ok(Err(error)) => {
+            error!("New database is invalid, still serving old one: {}", error);
+            return Err(error)
+        }
+        Err(_panic) => {
+            error!("Failed to load new database, still serving old one.");
+            return Err("panicked while loading database".to_string())
+        }
     };
+
+    *DATABASE.write().expect("database lock poisoned") = database;
+    info!("Database reloaded.");
+
+    Ok(())
+}
+
+/// Returns the most recent modification time of any normalized data file, or
+/// of the shard manifest, which is replaced once a new generation of shards
+/// has been written.
+fn latest_modification() -> Option<SystemTime> {
+    let shard_manifest = std::fs::metadata("data/normalized/shards/manifest.json")
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    std::fs::read_dir("data/normalized")
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .chain(shard_manifest)
+        .max()
+}
+
+/// Polls the normalized data directory forever, reloading the database once
+/// it has changed and then stopped changing for a full interval, so that we
+/// don't load a half-written set of tables.
+fn watch_for_changes() {
+    let mut loaded = latest_modification();
+    let mut pending = None;
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+
+        let modified = latest_modification();
+        if modified == loaded {
+            pending = None;
+        } else if modified == pending {
+            loaded = modified;
+            pending = None;
+            // errors are logged, and the old database continues serving
+            let _ = reload_database();
+        } else {
+            debug!("Normalized data changed, waiting for it to settle...");
+            pending = modified;
+        }
+    }
+}
+
+/// The token that `/admin/reload` requests must present as a bearer token, from
+/// `SPEEDRUNS_ADMIN_TOKEN`. The route isn't served if this isn't set.
+#[derive(Debug, Clone)]
+struct AdminToken(String);
+
+impl AdminToken {
+    fn from_env() -> Option<AdminToken> {
+        match std::env::var("SPEEDRUNS_ADMIN_TOKEN") {
+            Ok(token) if !token.is_empty() => Some(AdminToken(token)),
+            _ => None,
+        }
+    }
+
+    /// Whether the request has this token in its `Authorization` header,
+    /// compared in constant time.
+    fn authorizes(&self, request: &actix_web::HttpRequest) -> bool {
+        let presented = request
+            .headers()
+            .get(actix_web::http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| value.starts_with("Bearer "))
+            .map(|value| &value["Bearer ".len()..])
+            .unwrap_or_default();
+        presented.len() == self.0.len()
+            && presented
+                .bytes()
+                .zip(self.0.bytes())
+                .fold(0, |difference, (a, b)| difference | (a ^ b))
+                == 0
+    }
+}
+
+async fn admin_reload(
+    token: web::Data<AdminToken>,
+    request: actix_web::HttpRequest,
+) -> actix_web::HttpResponse {
+    if !token.authorizes(&request) {
+        return actix_web::HttpResponse::Unauthorized()
+            .content_type("text/plain; charset=utf-8")
+            .body("unauthorized\n")
+    }
+
+    match web::block(reload_database).await {
+        Ok(()) => actix_web::HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .body("reloaded\n"),
+        Err(error) => actix_web::HttpResponse::InternalServerError()
+            .content_type("text/plain; charset=utf-8")
+            .body(format!("reload failed: {:?}\n", error)),
+    }
 }
 
 async fn graphql(
     schema: web::Data<Arc<graphql::Schema>>,
     query: web::Json<GraphQLRequest>,
 ) -> actix_web::Result<actix_web::HttpResponse> {
-    let database = DATABASE.clone();
+    let context = current_database();
     let user = web::block(move || {
-        let res = query.execute(&schema, &graphql::Context { database });
+        let res = query.execute(&schema, &context);
         Ok::<_, serde_json::error::Error>(serde_json::to_string(&res)?)
     })
     .await?;
@@ -76,11 +233,26 @@ async fn main() -> std::io::Result<()> {
     info!("Initializing server.");
     lazy_static::initialize(&DATABASE);
 
+    info!("Watching for data changes.");
+    std::thread::spawn(watch_for_changes);
+
     info!("Initializing schema.");
     let schema = Arc::new(graphql::schema());
 
+    let admin_token = AdminToken::from_env();
+    if admin_token.is_none() {
+        info!("SPEEDRUNS_ADMIN_TOKEN isn't set, not serving /admin/reload.");
+    }
+
     info!("Initializing server.");
     let server = actix_web::HttpServer::new(move || {
+        let mut admin = web::scope("/admin");
+        if let Some(admin_token) = &admin_token {
+            admin = admin
+                .data(admin_token.clone())
+                .service(web::resource("/reload").route(web::post().to(admin_reload)));
+        }
+
         actix_web::App::new()
             .data(schema.clone())
             .wrap(actix_cors::Cors::new().finish())
@@ -89,49 +261,44 @@ async fn main() -> std::io::Result<()> {
             .service(web::resource("/graphiql").route(web::get().to(graphiql)))
             .service(web::resource("/playground").route(web::get().to(playground)))
             .service(web::resource("/diediedie").route(web::get().to(diediedie)))
+            .service(admin)
     });
 
     info!("Binding server.");
     server.bind("127.0.0.1:3001")?.run().await
 }
 
-fn unpack_bundled_tables() -> Tables {
-    info!("Unpacking bundled database...");
-
-    let runs = read_table("data/normalized/runs.jsonl").expect("run data corrupt");
-    info!("{} runs.", runs.len());
-    let users = read_table("data/normalized/users.jsonl").expect("user data corrupt");
-    info!("{} users.", users.len());
-    let games = read_table("data/normalized/games.jsonl").expect("game data corrupt");
-    info!("{} games.", games.len());
-    let categories =
-        read_table("data/normalized/categories.jsonl").expect("category data corrupt");
-    info!("{} categories.", categories.len());
-    let levels = read_table("data/normalized/levels.jsonl").expect("level data corrupt");
-    info!("{} levels.", levels.len());
-
-    Tables::new(runs, users, games, categories, levels)
-}
+fn unpack_bundled_tables() -> Result<Tables, Box<dyn std::error::Error>> {
+    info!("Loading database snapshot...");
 
-pub fn read_table<T: DeserializeOwned>(
-    path: &str,
-) -> Result<Vec<T>, Box<dyn std::error::Error>> {
-    let result: Result<Vec<T>, Box<dyn std::error::Error>> = try {
-        let file = File::open(path)?;
-        let buffer = BufReader::new(&file);
-        let deserializer = JsonDeserializer::from_reader(buffer);
-        let json_results = deserializer.into_iter::<JsonValue>();
-        json_results
-            .map(Result::unwrap)
-            .map(T::deserialize)
-            .map(Result::unwrap)
-            .collect()
-    };
-    match result {
-        Ok(result) => Ok(result),
-        Err(err) => {
-            error!("Failed to load table: {:?}", err);
-            Ok(vec![])
+    let tables = match Tables::load_fresh_snapshot(
+        "data/normalized/tables.bin",
+        "data/normalized",
+    ) {
+        Ok(tables) => tables,
+        Err(error) => {
+            warn!("Failed to load snapshot, falling back to JSONL: {}", error);
+            info!("Unpacking bundled database...");
+            let (tables, _report) = Tables::load_dir("data/normalized", LoadMode::Strict)?;
+            tables
         }
-    }
+    };
+
+    info!(
+        "{} runs, {} users, {} games, {} categories, {} levels, {} variables, {} platforms, \
+         {} regions, {} game tags, {} game relations, {} unverified runs.",
+        tables.runs().len(),
+        tables.users().len(),
+        tables.games().len(),
+        tables.categories().len(),
+        tables.levels().len(),
+        tables.variables().len(),
+        tables.platforms().len(),
+        tables.regions().len(),
+        tables.game_tags().len(),
+        tables.game_relations().len(),
+        tables.unverified_runs().len()
+    );
+
+    Ok(tables)
 }
@@ -0,0 +1,76 @@
+//! Export our normalized tables to SQLite, or import them back.
+#![allow(missing_docs, clippy::useless_attribute)]
+
+use std::sync::Arc;
+
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+
+use speedruns::data::database::{Database, Tables};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::try_init_from_env(
+        env_logger::Env::new()
+            .default_filter_or(format!("{}=info,speedruns=info", module_path!())),
+    )?;
+
+    let args = clap::App::new("sqlite")
+        .about("Converts our normalized tables to and from SQLite.")
+        .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            clap::Arg::with_name("snapshot")
+                .long("snapshot")
+                .value_name("PATH")
+                .default_value("data/normalized/tables.bin")
+                .help("The binary table snapshot to read from or write to."),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("export")
+                .about("Writes the snapshot's tables to a SQLite database.")
+                .arg(
+                    clap::Arg::with_name("database")
+                        .value_name("DATABASE")
+                        .default_value("data/normalized/speedruns.sqlite"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("import")
+                .about(
+                    "Validates a SQLite database's tables and writes them to the snapshot.",
+                )
+                .arg(
+                    clap::Arg::with_name("database")
+                        .value_name("DATABASE")
+                        .default_value("data/normalized/speedruns.sqlite"),
+                ),
+        )
+        .get_matches();
+
+    let snapshot = args.value_of("snapshot").expect("has default");
+
+    match args.subcommand() {
+        ("export", Some(args)) => {
+            let database = args.value_of("database").expect("has default");
+
+            info!("Loading {}...", snapshot);
+            let tables = Tables::load_snapshot(snapshot)?;
+            info!("Writing {}...", database);
+            tables.write_sqlite(database)?;
+        }
+        ("import", Some(args)) => {
+            let database = args.value_of("database").expect("has default");
+
+            info!("Loading {}...", database);
+            let tables = Arc::new(Tables::load_sqlite(database)?);
+            info!("Validating...");
+            if let Err(errors) = Database::new(tables.clone()) {
+                error!("{}", errors);
+                return Err("imported tables are invalid".into())
+            }
+            info!("Writing {}...", snapshot);
+            tables.write_snapshot(snapshot)?;
+        }
+        _ => unreachable!("subcommand is required"),
+    }
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::{
     collections::{BTreeMap, HashMap},
     fmt::{Debug, Display},
     ops::Deref,
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use derive_more::From;
@@ -14,7 +14,11 @@ use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationErrors};
 
 use crate::{
-    data::{models::*, types::*},
+    data::{
+        models::*,
+        search::{SearchHit, SearchIndex, SearchType},
+        types::*,
+    },
     utils::slugify,
 };
 
@@ -80,42 +84,141 @@ pub enum IntegrityError {
 }
 
 /// All of the speedrun data in our normalized format.bash, indexed by ID.
+///
+/// Rows are reference-counted so that [Linked] values and [Database] indexes
+/// can share them without borrowing from the tables.
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Getters)]
 #[get = "pub"]
 pub struct Tables {
-    runs:       BTreeMap<u64, Run>,
-    users:      BTreeMap<u64, User>,
-    games:      BTreeMap<u64, Game>,
-    categories: BTreeMap<u64, Category>,
-    levels:     BTreeMap<u64, Level>,
+    runs:            BTreeMap<u64, Arc<Run>>,
+    users:           BTreeMap<u64, Arc<User>>,
+    games:           BTreeMap<u64, Arc<Game>>,
+    categories:      BTreeMap<u64, Arc<Category>>,
+    levels:          BTreeMap<u64, Arc<Level>>,
+    variables:       BTreeMap<u64, Arc<Variable>>,
+    platforms:       BTreeMap<u64, Arc<Platform>>,
+    regions:         BTreeMap<u64, Arc<Region>>,
+    game_tags:       BTreeMap<u64, Arc<GameTag>>,
+    /// Keyed by game ID.
+    game_relations:  BTreeMap<u64, Arc<GameRelation>>,
+    /// Keyed by run ID.
+    unverified_runs: BTreeMap<u64, Arc<UnverifiedRun>>,
 }
 
 impl Tables {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         runs: Vec<Run>,
         users: Vec<User>,
         games: Vec<Game>,
         categories: Vec<Category>,
         levels: Vec<Level>,
+        variables: Vec<Variable>,
+        platforms: Vec<Platform>,
+        regions: Vec<Region>,
+        game_tags: Vec<GameTag>,
+        game_relations: Vec<GameRelation>,
+        unverified_runs: Vec<UnverifiedRun>,
     ) -> Self {
         let mut self_ = Self::default();
         for run in runs {
-            self_.runs.insert(*run.id(), run);
+            self_.runs.insert(*run.id(), Arc::new(run));
         }
         for user in users {
-            self_.users.insert(*user.id(), user);
+            self_.users.insert(*user.id(), Arc::new(user));
         }
         for game in games {
-            self_.games.insert(*game.id(), game);
+            self_.games.insert(*game.id(), Arc::new(game));
         }
         for category in categories {
-            self_.categories.insert(*category.id(), category);
+            self_.categories.insert(*category.id(), Arc::new(category));
         }
         for level in levels {
-            self_.levels.insert(*level.id(), level);
+            self_.levels.insert(*level.id(), Arc::new(level));
+        }
+        for variable in variables {
+            self_.variables.insert(*variable.id(), Arc::new(variable));
+        }
+        for platform in platforms {
+            self_.platforms.insert(*platform.id(), Arc::new(platform));
+        }
+        for region in regions {
+            self_.regions.insert(*region.id(), Arc::new(region));
+        }
+        for tag in game_tags {
+            self_.game_tags.insert(*tag.id(), Arc::new(tag));
+        }
+        for relation in game_relations {
+            self_
+                .game_relations
+                .insert(*relation.game_id(), Arc::new(relation));
+        }
+        for run in unverified_runs {
+            self_.unverified_runs.insert(*run.run.id(), Arc::new(run));
         }
         self_
     }
+
+    /// Inserts a record of any type, replacing any existing record of the same
+    /// type with the same ID.
+    pub fn upsert(&mut self, record: AnyModel) {
+        match record {
+            AnyModel::Run(run) => {
+                self.runs.insert(*run.id(), Arc::new(run));
+            }
+            AnyModel::User(user) => {
+                self.users.insert(*user.id(), Arc::new(user));
+            }
+            AnyModel::Game(game) => {
+                self.games.insert(*game.id(), Arc::new(game));
+            }
+            AnyModel::Category(category) => {
+                self.categories.insert(*category.id(), Arc::new(category));
+            }
+            AnyModel::Level(level) => {
+                self.levels.insert(*level.id(), Arc::new(level));
+            }
+            AnyModel::Variable(variable) => {
+                self.variables.insert(*variable.id(), Arc::new(variable));
+            }
+            AnyModel::Platform(platform) => {
+                self.platforms.insert(*platform.id(), Arc::new(platform));
+            }
+            AnyModel::Region(region) => {
+                self.regions.insert(*region.id(), Arc::new(region));
+            }
+            AnyModel::GameTag(tag) => {
+                self.game_tags.insert(*tag.id(), Arc::new(tag));
+            }
+            AnyModel::GameRelation(relation) => {
+                self.game_relations
+                    .insert(*relation.game_id(), Arc::new(relation));
+            }
+            AnyModel::UnverifiedRun(run) => {
+                self.unverified_runs.insert(*run.run.id(), Arc::new(run));
+            }
+        }
+    }
+
+    /// Removes the record with the same type and ID as the given one, returning
+    /// whether it was present.
+    pub fn remove(&mut self, record: &AnyModel) -> bool {
+        match record {
+            AnyModel::Run(run) => self.runs.remove(run.id()).is_some(),
+            AnyModel::User(user) => self.users.remove(user.id()).is_some(),
+            AnyModel::Game(game) => self.games.remove(game.id()).is_some(),
+            AnyModel::Category(category) => self.categories.remove(category.id()).is_some(),
+            AnyModel::Level(level) => self.levels.remove(level.id()).is_some(),
+            AnyModel::Variable(variable) => self.variables.remove(variable.id()).is_some(),
+            AnyModel::Platform(platform) => self.platforms.remove(platform.id()).is_some(),
+            AnyModel::Region(region) => self.regions.remove(region.id()).is_some(),
+            AnyModel::GameTag(tag) => self.game_tags.remove(tag.id()).is_some(),
+            AnyModel::GameRelation(relation) =>
+                self.game_relations.remove(relation.game_id()).is_some(),
+            AnyModel::UnverifiedRun(run) =>
+                self.unverified_runs.remove(run.run.id()).is_some(),
+        }
+    }
 }
 
 /// Panic message used when the database state is invalid but that shouldn't be
@@ -125,14 +228,27 @@ const DATABASE_INTEGRITY: &str = "Database state invalid despite passing validat
 
 /// A collection of [Tables] with various generated indexes.
 pub struct Database {
-    tables:                                   &'static Tables,
-    runs_by_game_id:                          HashMap<u64, Vec<&'static Run>>,
-    games_by_slug:                            HashMap<String, &'static Game>,
-    users_by_slug:                            HashMap<String, &'static User>,
-    per_game_categories_by_game_id_and_slug:  HashMap<(u64, String), &'static Category>,
-    per_level_categories_by_game_id_and_slug: HashMap<(u64, String), &'static Category>,
-    levels_by_game_id_and_slug:               HashMap<(u64, String), &'static Level>,
-    _runs_by_category_level_and_slug: HashMap<(u64, Option<u64>, String), &'static Run>,
+    tables:                                   Arc<Tables>,
+    runs_by_game_id:                          HashMap<u64, Vec<Arc<Run>>>,
+    games_by_slug:                            HashMap<String, Arc<Game>>,
+    users_by_slug:                            HashMap<String, Arc<User>>,
+    per_game_categories_by_game_id_and_slug:  HashMap<(u64, String), Arc<Category>>,
+    per_level_categories_by_game_id_and_slug: HashMap<(u64, String), Arc<Category>>,
+    levels_by_game_id_and_slug:               HashMap<(u64, String), Arc<Level>>,
+    categories_by_game_id:                    HashMap<u64, Vec<Arc<Category>>>,
+    levels_by_game_id:                        HashMap<u64, Vec<Arc<Level>>>,
+    runs_by_category_id_and_level_id:         HashMap<(u64, Option<u64>), Vec<Arc<Run>>>,
+    runs_by_user_id:                          HashMap<u64, Vec<Arc<Run>>>,
+    variables_by_game_id:                     HashMap<u64, Vec<Arc<Variable>>>,
+    platforms_by_slug:                        HashMap<String, Arc<Platform>>,
+    regions_by_slug:                          HashMap<String, Arc<Region>>,
+    game_tags_by_kind_and_slug:               HashMap<(GameTagKind, String), Arc<GameTag>>,
+    games_by_tag_id:                          HashMap<u64, Vec<Arc<Game>>>,
+    derived_games_by_base_game_id:            HashMap<u64, Vec<Arc<Game>>>,
+    games_by_series_id:                       HashMap<u64, Vec<Arc<Game>>>,
+    games_by_moderator_id:                    HashMap<u64, Vec<Arc<Game>>>,
+    unverified_runs_by_game_id:               HashMap<u64, Vec<Arc<UnverifiedRun>>>,
+    search_index:                             Mutex<Option<Arc<SearchIndex>>>,
 }
 
 impl std::fmt::Debug for Database {
@@ -144,47 +260,100 @@ impl std::fmt::Debug for Database {
 impl Database {
     fn link<ModelType: Model>(
         self: &Arc<Self>,
-        item: &'static ModelType,
+        item: &Arc<ModelType>,
     ) -> Linked<ModelType> {
-        Linked::new(self.clone(), item)
+        Linked::new(self.clone(), item.clone())
     }
 
-    /// Creates a new Database indexing a collection of static tables.
-    pub fn new(tables: &'static Tables) -> Result<Arc<Self>, IntegrityErrors> {
-        let mut runs_by_game_id: HashMap<u64, Vec<&'static Run>> = HashMap::new();
-        let mut games_by_slug: HashMap<String, &'static Game> = HashMap::new();
-        let mut users_by_slug: HashMap<String, &'static User> = HashMap::new();
+    /// Creates a new Database indexing a collection of tables.
+    ///
+    /// The Database shares ownership of the tables, so they're freed once the
+    /// Database and every [Linked] value referring to it have been dropped.
+    pub fn new(tables: Arc<Tables>) -> Result<Arc<Self>, IntegrityErrors> {
+        let mut runs_by_game_id: HashMap<u64, Vec<Arc<Run>>> = HashMap::new();
+        let mut games_by_slug: HashMap<String, Arc<Game>> = HashMap::new();
+        let mut users_by_slug: HashMap<String, Arc<User>> = HashMap::new();
         let mut per_game_categories_by_game_id_and_slug: HashMap<
             (u64, String),
-            &'static Category,
+            Arc<Category>,
         > = HashMap::new();
         let mut per_level_categories_by_game_id_and_slug: HashMap<
             (u64, String),
-            &'static Category,
+            Arc<Category>,
         > = HashMap::new();
-        let mut levels_by_game_id_and_slug: HashMap<(u64, String), &'static Level> =
+        let mut levels_by_game_id_and_slug: HashMap<(u64, String), Arc<Level>> =
             HashMap::new();
-        let _runs_by_category_level_and_slug: HashMap<
-            (u64, Option<u64>, String),
-            &'static Run,
+        let mut categories_by_game_id: HashMap<u64, Vec<Arc<Category>>> = HashMap::new();
+        let mut levels_by_game_id: HashMap<u64, Vec<Arc<Level>>> = HashMap::new();
+        let mut runs_by_category_id_and_level_id: HashMap<
+            (u64, Option<u64>),
+            Vec<Arc<Run>>,
         > = HashMap::new();
+        let mut runs_by_user_id: HashMap<u64, Vec<Arc<Run>>> = HashMap::new();
+        let mut variables_by_game_id: HashMap<u64, Vec<Arc<Variable>>> = HashMap::new();
+        let mut platforms_by_slug: HashMap<String, Arc<Platform>> = HashMap::new();
+        let mut regions_by_slug: HashMap<String, Arc<Region>> = HashMap::new();
+        let mut game_tags_by_kind_and_slug: HashMap<(GameTagKind, String), Arc<GameTag>> =
+            HashMap::new();
+        let mut games_by_tag_id: HashMap<u64, Vec<Arc<Game>>> = HashMap::new();
+        let mut derived_games_by_base_game_id: HashMap<u64, Vec<Arc<Game>>> =
+            HashMap::new();
+        let mut games_by_series_id: HashMap<u64, Vec<Arc<Game>>> = HashMap::new();
+        let mut games_by_moderator_id: HashMap<u64, Vec<Arc<Game>>> = HashMap::new();
+        let mut unverified_runs_by_game_id: HashMap<u64, Vec<Arc<UnverifiedRun>>> =
+            HashMap::new();
 
         let index_errored = 'indexing: {
             for game in tables.games().values() {
                 runs_by_game_id.insert(*game.id(), Vec::new());
-                games_by_slug.insert(game.slug().to_string(), game);
+                games_by_slug.insert(game.slug().to_string(), game.clone());
+
+                for tag_id in game.tag_ids() {
+                    games_by_tag_id
+                        .entry(*tag_id)
+                        .or_insert_with(Vec::new)
+                        .push(game.clone());
+                }
+
+                for moderator in game.moderators() {
+                    games_by_moderator_id
+                        .entry(*moderator.user_id())
+                        .or_insert_with(Vec::new)
+                        .push(game.clone());
+                }
             }
 
             for run in tables.runs().values() {
                 if let Some(runs) = runs_by_game_id.get_mut(run.game_id()) {
-                    runs.push(run);
+                    runs.push(run.clone());
                 } else {
                     break 'indexing true
                 }
+
+                runs_by_category_id_and_level_id
+                    .entry((*run.category_id(), *run.level_id()))
+                    .or_insert_with(Vec::new)
+                    .push(run.clone());
+
+                for player in run.players() {
+                    if let RunPlayer::UserId(user_id) = player {
+                        runs_by_user_id
+                            .entry(*user_id)
+                            .or_insert_with(Vec::new)
+                            .push(run.clone());
+                    }
+                }
+            }
+
+            for run in tables.unverified_runs().values() {
+                unverified_runs_by_game_id
+                    .entry(*run.run.game_id())
+                    .or_insert_with(Vec::new)
+                    .push(run.clone());
             }
 
             for user in tables.users().values() {
-                users_by_slug.insert(user.slug().to_string(), user);
+                users_by_slug.insert(user.slug().to_string(), user.clone());
             }
 
             for category in tables.categories().values() {
@@ -192,18 +361,112 @@ impl Database {
                     CategoryType::PerGame => &mut per_game_categories_by_game_id_and_slug,
                     CategoryType::PerLevel => &mut per_level_categories_by_game_id_and_slug,
                 }
-                .insert((*category.game_id(), category.slug().to_string()), category);
+                .insert(
+                    (*category.game_id(), category.slug().to_string()),
+                    category.clone(),
+                );
+
+                categories_by_game_id
+                    .entry(*category.game_id())
+                    .or_insert_with(Vec::new)
+                    .push(category.clone());
             }
 
             for level in tables.levels().values() {
                 levels_by_game_id_and_slug
-                    .insert((*level.game_id(), level.slug().to_string()), level);
+                    .insert((*level.game_id(), level.slug().to_string()), level.clone());
+
+                levels_by_game_id
+                    .entry(*level.game_id())
+                    .or_insert_with(Vec::new)
+                    .push(level.clone());
+            }
+
+            for variable in tables.variables().values() {
+                variables_by_game_id
+                    .entry(*variable.game_id())
+                    .or_insert_with(Vec::new)
+                    .push(variable.clone());
+            }
+
+            for platform in tables.platforms().values() {
+                platforms_by_slug.insert(platform.slug().to_string(), platform.clone());
+            }
+
+            for region in tables.regions().values() {
+                regions_by_slug.insert(region.slug().to_string(), region.clone());
+            }
+
+            for tag in tables.game_tags().values() {
+                game_tags_by_kind_and_slug
+                    .insert((*tag.kind(), tag.slug().to_string()), tag.clone());
+            }
+
+            for relation in tables.game_relations().values() {
+                let game = match tables.games().get(relation.game_id()) {
+                    Some(game) => game,
+                    None => break 'indexing true,
+                };
+
+                if let Some(base_game_id) = relation.base_game_id() {
+                    derived_games_by_base_game_id
+                        .entry(*base_game_id)
+                        .or_insert_with(Vec::new)
+                        .push(game.clone());
+                }
+
+                for series_id in relation.series_ids() {
+                    games_by_series_id
+                        .entry(*series_id)
+                        .or_insert_with(Vec::new)
+                        .push(game.clone());
+                }
             }
 
             for game_runs in runs_by_game_id.values_mut() {
                 game_runs.sort();
             }
 
+            for runs in runs_by_category_id_and_level_id.values_mut() {
+                runs.sort();
+            }
+
+            for user_runs in runs_by_user_id.values_mut() {
+                user_runs.sort();
+            }
+
+            for game_categories in categories_by_game_id.values_mut() {
+                game_categories.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for game_levels in levels_by_game_id.values_mut() {
+                game_levels.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for game_variables in variables_by_game_id.values_mut() {
+                game_variables.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for tag_games in games_by_tag_id.values_mut() {
+                tag_games.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for derived_games in derived_games_by_base_game_id.values_mut() {
+                derived_games.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for series_games in games_by_series_id.values_mut() {
+                series_games.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for moderated_games in games_by_moderator_id.values_mut() {
+                moderated_games.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            }
+
+            for game_runs in unverified_runs_by_game_id.values_mut() {
+                game_runs.sort();
+            }
+
             false
         };
 
@@ -221,7 +484,20 @@ impl Database {
             per_game_categories_by_game_id_and_slug,
             per_level_categories_by_game_id_and_slug,
             levels_by_game_id_and_slug,
-            _runs_by_category_level_and_slug,
+            categories_by_game_id,
+            levels_by_game_id,
+            runs_by_category_id_and_level_id,
+            runs_by_user_id,
+            variables_by_game_id,
+            platforms_by_slug,
+            regions_by_slug,
+            game_tags_by_kind_and_slug,
+            games_by_tag_id,
+            derived_games_by_base_game_id,
+            games_by_series_id,
+            games_by_moderator_id,
+            unverified_runs_by_game_id,
+            search_index: Mutex::new(None),
         });
 
         if let Err(mut errors_) = self_.validate() {
@@ -332,11 +608,137 @@ impl Database {
             }
         }
 
+        trace!("Validating {} variables.", self.tables.variables().len());
+        for variable in self.variables() {
+            if let Err(mut error) = variable.validate() {
+                errors.append(&mut error.errors);
+            }
+        }
+
+        trace!("Validating {} platforms.", self.tables.platforms().len());
+        let mut platform_slugs = HashMap::<String, Vec<Platform>>::new();
+        for platform in self.platforms() {
+            if let Err(validation_errors) = platform.validate() {
+                errors.push(IntegrityError::CheckFailed {
+                    errors: validation_errors,
+                    source: Platform::clone(&*platform).into(),
+                });
+            } else {
+                platform_slugs
+                    .entry(platform.slug().to_string())
+                    .or_insert_with(Vec::new)
+                    .push(Platform::clone(&*platform));
+            }
+        }
+        for (slug, items) in platform_slugs {
+            if items.len() >= 2 {
+                errors.push(IntegrityError::NonUniqueSlug {
+                    slug,
+                    sources: AnyModelVec::Platforms(items),
+                });
+            }
+        }
+
+        trace!("Validating {} regions.", self.tables.regions().len());
+        let mut region_slugs = HashMap::<String, Vec<Region>>::new();
+        for region in self.regions() {
+            if let Err(validation_errors) = region.validate() {
+                errors.push(IntegrityError::CheckFailed {
+                    errors: validation_errors,
+                    source: Region::clone(&*region).into(),
+                });
+            } else {
+                region_slugs
+                    .entry(region.slug().to_string())
+                    .or_insert_with(Vec::new)
+                    .push(Region::clone(&*region));
+            }
+        }
+        for (slug, items) in region_slugs {
+            if items.len() >= 2 {
+                errors.push(IntegrityError::NonUniqueSlug {
+                    slug,
+                    sources: AnyModelVec::Regions(items),
+                });
+            }
+        }
+
+        trace!("Validating {} game tags.", self.tables.game_tags().len());
+        let mut tag_slugs = HashMap::<String, Vec<GameTag>>::new();
+        for tag in self.game_tags() {
+            if let Err(validation_errors) = tag.validate() {
+                errors.push(IntegrityError::CheckFailed {
+                    errors: validation_errors,
+                    source: GameTag::clone(&*tag).into(),
+                });
+            } else {
+                let slug =
+                    format!("{}/{}", slugify(&format!("{:?}", tag.kind())), tag.slug());
+                tag_slugs
+                    .entry(slug)
+                    .or_insert_with(Vec::new)
+                    .push(GameTag::clone(&*tag));
+            }
+        }
+        for (slug, items) in tag_slugs {
+            if items.len() >= 2 {
+                errors.push(IntegrityError::NonUniqueSlug {
+                    slug,
+                    sources: AnyModelVec::GameTags(items),
+                });
+            }
+        }
+
+        trace!(
+            "Validating {} game relations.",
+            self.tables.game_relations().len()
+        );
+        for relation in self.game_relations() {
+            if let Err(mut error) = relation.validate() {
+                errors.append(&mut error.errors);
+            }
+        }
+
+        trace!(
+            "Validating {} unverified runs.",
+            self.tables.unverified_runs().len()
+        );
+        for run in self.unverified_runs() {
+            if let Err(mut error) = run.validate() {
+                errors.append(&mut error.errors);
+            }
+        }
+
         IntegrityErrors::try_from(errors)
     }
 
+    /// Returns the search index of this database's names, building it if this
+    /// is the first time it's been used.
+    pub fn search_index(self: &Arc<Self>) -> Arc<SearchIndex> {
+        self.search_index
+            .lock()
+            .expect("search index lock poisoned")
+            .get_or_insert_with(|| {
+                info!("Building search index...");
+                Arc::new(SearchIndex::new(&self.tables))
+            })
+            .clone()
+    }
+
+    /// Returns up to `limit` games, categories, levels, and users whose names
+    /// match `query`, best first, including only the given `types` of results
+    /// (or all types if it's empty).
+    pub fn search(
+        self: &Arc<Self>,
+        query: &str,
+        types: &[SearchType],
+        limit: usize,
+    ) -> Vec<SearchHit> {
+        self.search_index().search(self, query, types, limit)
+    }
+
     /// Iterator over all Linked<Run>s.
-    pub fn runs(self: &Arc<Self>) -> impl Iterator<Item = Linked<Run>> {
+    pub fn runs(self: &Arc<Self>) -> impl Iterator<Item = Linked<Run>> + '_ {
         let self_ = self.clone();
         self.tables.runs().values().map(move |run| self_.link(run))
     }
@@ -351,11 +753,33 @@ impl Database {
     pub fn runs_by_game_id(self: &Arc<Self>, game_id: u64) -> Option<Vec<Linked<Run>>> {
         self.runs_by_game_id
             .get(&game_id)
-            .map(|ref runs| runs.iter().map(|run| self.link(*run)).collect())
+            .map(|ref runs| runs.iter().map(|run| self.link(run)).collect())
+    }
+
+    /// Returns a Vec of Linked<Run> for a given category ID and level ID (None
+    /// for full-game runs), sorted by ID.
+    pub fn runs_by_category_id_and_level_id(
+        self: &Arc<Self>,
+        category_id: u64,
+        level_id: Option<u64>,
+    ) -> Vec<Linked<Run>> {
+        self.runs_by_category_id_and_level_id
+            .get(&(category_id, level_id))
+            .map(|runs| runs.iter().map(|run| self.link(run)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns a Vec of Linked<Run> that a given user ID participated in,
+    /// sorted by game, category, and level.
+    pub fn runs_by_user_id(self: &Arc<Self>, user_id: u64) -> Vec<Linked<Run>> {
+        self.runs_by_user_id
+            .get(&user_id)
+            .map(|runs| runs.iter().map(|run| self.link(run)).collect())
+            .unwrap_or_default()
     }
 
     /// Iterator over all Linked<User>s.
-    pub fn users(self: &Arc<Self>) -> impl Iterator<Item = Linked<User>> {
+    pub fn users(self: &Arc<Self>) -> impl Iterator<Item = Linked<User>> + '_ {
         let self_ = self.clone();
         self.tables
             .users()
@@ -371,11 +795,11 @@ impl Database {
     /// Finds a Linked<User> by name.
     pub fn user_by_slug(self: &Arc<Self>, slug: &str) -> Option<Linked<User>> {
         // TODO: stop all indexing by slugify, let consumers do that if they want.
-        self.users_by_slug.get(slug).map(|user| self.link(*user))
+        self.users_by_slug.get(slug).map(|user| self.link(user))
     }
 
     /// Iterator over all Linked<Game>s.
-    pub fn games(self: &Arc<Self>) -> impl Iterator<Item = Linked<Game>> {
+    pub fn games(self: &Arc<Self>) -> impl Iterator<Item = Linked<Game>> + '_ {
         let self_ = self.clone();
         self.tables
             .games()
@@ -390,7 +814,7 @@ impl Database {
 
     /// Finds a Linked<Game> by slug.
     pub fn game_by_slug(self: &Arc<Self>, slug: &str) -> Option<Linked<Game>> {
-        self.games_by_slug.get(slug).map(|game| self.link(*game))
+        self.games_by_slug.get(slug).map(|game| self.link(game))
     }
 
     /// Finds a level with the given name and game ID.
@@ -401,11 +825,19 @@ impl Database {
     ) -> Option<Linked<Level>> {
         self.levels_by_game_id_and_slug
             .get(&(game_id, slug.to_string()))
-            .map(|level| self.link(*level))
+            .map(|level| self.link(level))
+    }
+
+    /// Returns a Vec of Linked<Level> for a given game ID, sorted by name.
+    pub fn levels_by_game_id(self: &Arc<Self>, game_id: u64) -> Vec<Linked<Level>> {
+        self.levels_by_game_id
+            .get(&game_id)
+            .map(|levels| levels.iter().map(|level| self.link(level)).collect())
+            .unwrap_or_default()
     }
 
     /// Iterator over all Linked<Level>s.
-    pub fn levels(self: &Arc<Self>) -> impl Iterator<Item = Linked<Level>> {
+    pub fn levels(self: &Arc<Self>) -> impl Iterator<Item = Linked<Level>> + '_ {
         let self_ = self.clone();
         self.tables
             .levels()
@@ -434,7 +866,7 @@ impl Database {
     ) -> Option<Linked<Category>> {
         self.per_game_categories_by_game_id_and_slug
             .get(&(game_id, slug.to_string()))
-            .map(|category| self.link(*category))
+            .map(|category| self.link(category))
     }
 
     /// Finds a per-level category with the given slug and game ID.
@@ -445,17 +877,226 @@ impl Database {
     ) -> Option<Linked<Category>> {
         self.per_level_categories_by_game_id_and_slug
             .get(&(game_id, slug.to_string()))
-            .map(|category| self.link(*category))
+            .map(|category| self.link(category))
+    }
+
+    /// Returns a Vec of Linked<Category> for a given game ID, sorted by name.
+    pub fn categories_by_game_id(self: &Arc<Self>, game_id: u64) -> Vec<Linked<Category>> {
+        self.categories_by_game_id
+            .get(&game_id)
+            .map(|categories| {
+                categories
+                    .iter()
+                    .map(|category| self.link(category))
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     /// Iterator over all Linked<Category>s.
-    pub fn categories(self: &Arc<Self>) -> impl Iterator<Item = Linked<Category>> {
+    pub fn categories(self: &Arc<Self>) -> impl Iterator<Item = Linked<Category>> + '_ {
         let self_ = self.clone();
         self.tables
             .categories()
             .values()
             .map(move |category| self_.link(category))
     }
+
+    /// Iterator over all Linked<Variable>s.
+    pub fn variables(self: &Arc<Self>) -> impl Iterator<Item = Linked<Variable>> + '_ {
+        let self_ = self.clone();
+        self.tables
+            .variables()
+            .values()
+            .map(move |variable| self_.link(variable))
+    }
+
+    /// Finds a Linked<Variable> by id.
+    pub fn variable_by_id(self: &Arc<Self>, id: u64) -> Option<Linked<Variable>> {
+        self.tables
+            .variables()
+            .get(&id)
+            .map(|variable| self.link(variable))
+    }
+
+    /// Returns a Vec of Linked<Variable> for a given game ID, sorted by name.
+    pub fn variables_by_game_id(self: &Arc<Self>, game_id: u64) -> Vec<Linked<Variable>> {
+        self.variables_by_game_id
+            .get(&game_id)
+            .map(|variables| {
+                variables
+                    .iter()
+                    .map(|variable| self.link(variable))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Iterator over all Linked<Platform>s.
+    pub fn platforms(self: &Arc<Self>) -> impl Iterator<Item = Linked<Platform>> + '_ {
+        let self_ = self.clone();
+        self.tables
+            .platforms()
+            .values()
+            .map(move |platform| self_.link(platform))
+    }
+
+    /// Finds a Linked<Platform> by id.
+    pub fn platform_by_id(self: &Arc<Self>, id: u64) -> Option<Linked<Platform>> {
+        self.tables
+            .platforms()
+            .get(&id)
+            .map(|platform| self.link(platform))
+    }
+
+    /// Finds a Linked<Platform> by slug.
+    pub fn platform_by_slug(self: &Arc<Self>, slug: &str) -> Option<Linked<Platform>> {
+        self.platforms_by_slug
+            .get(slug)
+            .map(|platform| self.link(platform))
+    }
+
+    /// Iterator over all Linked<Region>s.
+    pub fn regions(self: &Arc<Self>) -> impl Iterator<Item = Linked<Region>> + '_ {
+        let self_ = self.clone();
+        self.tables
+            .regions()
+            .values()
+            .map(move |region| self_.link(region))
+    }
+
+    /// Finds a Linked<Region> by id.
+    pub fn region_by_id(self: &Arc<Self>, id: u64) -> Option<Linked<Region>> {
+        self.tables
+            .regions()
+            .get(&id)
+            .map(|region| self.link(region))
+    }
+
+    /// Finds a Linked<Region> by slug.
+    pub fn region_by_slug(self: &Arc<Self>, slug: &str) -> Option<Linked<Region>> {
+        self.regions_by_slug
+            .get(slug)
+            .map(|region| self.link(region))
+    }
+
+    /// Iterator over all Linked<GameTag>s.
+    pub fn game_tags(self: &Arc<Self>) -> impl Iterator<Item = Linked<GameTag>> + '_ {
+        let self_ = self.clone();
+        self.tables
+            .game_tags()
+            .values()
+            .map(move |tag| self_.link(tag))
+    }
+
+    /// Finds a Linked<GameTag> by id.
+    pub fn game_tag_by_id(self: &Arc<Self>, id: u64) -> Option<Linked<GameTag>> {
+        self.tables.game_tags().get(&id).map(|tag| self.link(tag))
+    }
+
+    /// Finds a Linked<GameTag> by kind and slug.
+    pub fn game_tag_by_kind_and_slug(
+        self: &Arc<Self>,
+        kind: GameTagKind,
+        slug: &str,
+    ) -> Option<Linked<GameTag>> {
+        self.game_tags_by_kind_and_slug
+            .get(&(kind, slug.to_string()))
+            .map(|tag| self.link(tag))
+    }
+
+    /// Iterator over all Linked<GameRelation>s.
+    pub fn game_relations(
+        self: &Arc<Self>,
+    ) -> impl Iterator<Item = Linked<GameRelation>> + '_ {
+        let self_ = self.clone();
+        self.tables
+            .game_relations()
+            .values()
+            .map(move |relation| self_.link(relation))
+    }
+
+    /// Finds a Linked<GameRelation> by the ID of its game.
+    pub fn game_relation_by_game_id(
+        self: &Arc<Self>,
+        game_id: u64,
+    ) -> Option<Linked<GameRelation>> {
+        self.tables
+            .game_relations()
+            .get(&game_id)
+            .map(|relation| self.link(relation))
+    }
+
+    /// Returns a Vec of Linked<Game> derived from a given game ID, sorted by
+    /// name.
+    pub fn derived_games_by_base_game_id(
+        self: &Arc<Self>,
+        base_game_id: u64,
+    ) -> Vec<Linked<Game>> {
+        self.derived_games_by_base_game_id
+            .get(&base_game_id)
+            .map(|games| games.iter().map(|game| self.link(game)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns a Vec of Linked<Game> in a given series ID, sorted by name.
+    pub fn games_by_series_id(self: &Arc<Self>, series_id: u64) -> Vec<Linked<Game>> {
+        self.games_by_series_id
+            .get(&series_id)
+            .map(|games| games.iter().map(|game| self.link(game)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns a Vec of Linked<Game> with a given tag ID, sorted by name.
+    pub fn games_by_tag_id(self: &Arc<Self>, tag_id: u64) -> Vec<Linked<Game>> {
+        self.games_by_tag_id
+            .get(&tag_id)
+            .map(|games| games.iter().map(|game| self.link(game)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns a Vec of Linked<Game> moderated by a given user ID, sorted by
+    /// name.
+    pub fn games_by_moderator_id(self: &Arc<Self>, user_id: u64) -> Vec<Linked<Game>> {
+        self.games_by_moderator_id
+            .get(&user_id)
+            .map(|games| games.iter().map(|game| self.link(game)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Iterator over all Linked<UnverifiedRun>s.
+    pub fn unverified_runs(
+        self: &Arc<Self>,
+    ) -> impl Iterator<Item = Linked<UnverifiedRun>> + '_ {
+        let self_ = self.clone();
+        self.tables
+            .unverified_runs()
+            .values()
+            .map(move |run| self_.link(run))
+    }
+
+    /// Finds a Linked<UnverifiedRun> by id.
+    pub fn unverified_run_by_id(
+        self: &Arc<Self>,
+        id: u64,
+    ) -> Option<Linked<UnverifiedRun>> {
+        self.tables
+            .unverified_runs()
+            .get(&id)
+            .map(|run| self.link(run))
+    }
+
+    /// Returns a Vec of Linked<UnverifiedRun> for a given game ID, sorted by
+    /// ID.
+    pub fn unverified_runs_by_game_id(
+        self: &Arc<Self>,
+        game_id: u64,
+    ) -> Vec<Linked<UnverifiedRun>> {
+        self.unverified_runs_by_game_id
+            .get(&game_id)
+            .map(|runs| runs.iter().map(|run| self.link(run)).collect())
+            .unwrap_or_default()
+    }
 }
 
 /// Wraps [Model] types to add references to the Database, adding new
@@ -465,24 +1106,29 @@ pub struct Linked<ModelType: 'static + Model + Debug> {
     #[serde(skip)]
     database: Arc<Database>,
     #[serde(flatten)]
-    item:     &'static ModelType,
+    item:     Arc<ModelType>,
 }
 
 impl<ModelType: Model> Linked<ModelType> {
-    pub fn new(database: Arc<Database>, item: &'static ModelType) -> Self {
+    pub fn new(database: Arc<Database>, item: Arc<ModelType>) -> Self {
         Self { database, item }
     }
 
-    /// Returns the underlying static model instance reference.
-    pub fn as_static(&self) -> &'static ModelType {
-        self.item
+    /// Returns the shared underlying model instance.
+    pub fn as_arc(&self) -> &Arc<ModelType> {
+        &self.item
+    }
+
+    /// Returns the database this instance is linked to.
+    pub fn database(&self) -> &Arc<Database> {
+        &self.database
     }
 }
 
 impl<ModelType: Model> Deref for Linked<ModelType> {
     type Target = ModelType;
 
-    /// Deref to enable method delegation, but this loses the 'static lifetime.
+    /// Deref to enable method delegation.
     fn deref(&self) -> &ModelType {
         &self.item
     }
@@ -541,6 +1187,28 @@ impl Linked<Run> {
         }
     }
 
+    /// Returns Some(Linked<Platform>) for this Run, or None if it wasn't
+    /// specified.
+    pub fn platform(&self) -> Option<Linked<Platform>> {
+        self.platform_id().map(|platform_id| {
+            self.database
+                .clone()
+                .platform_by_id(platform_id)
+                .expect(DATABASE_INTEGRITY)
+        })
+    }
+
+    /// Returns Some(Linked<Region>) for this Run, or None if it wasn't
+    /// specified.
+    pub fn region(&self) -> Option<Linked<Region>> {
+        self.region_id().map(|region_id| {
+            self.database
+                .clone()
+                .region_by_id(region_id)
+                .expect(DATABASE_INTEGRITY)
+        })
+    }
+
     /// Returns Vec<Linked<User>> for this Run. May be empty if all runners
     /// unregistered/guests.
     pub fn users(&self) -> Vec<Linked<User>> {
@@ -558,75 +1226,218 @@ impl Linked<Run> {
             .collect()
     }
 
+    /// Returns the ID of this run's value for the given variable, falling back
+    /// to the variable's default value if the run doesn't have one.
+    pub fn value_id(&self, variable: &Variable) -> Option<u64> {
+        self.values()
+            .get(variable.id())
+            .copied()
+            .or(*variable.default_value_id())
+    }
+
+    /// Returns whether this run has every one of the given value IDs, by
+    /// variable ID.
+    pub fn has_values(&self, values: &BTreeMap<u64, u64>) -> bool {
+        values.iter().all(|(variable_id, value_id)| {
+            match self.database.variable_by_id(*variable_id) {
+                Some(variable) => self.value_id(&variable) == Some(*value_id),
+                None => false,
+            }
+        })
+    }
+
+    /// Returns this run's value IDs for each of the subcategory variables that
+    /// apply to it, by variable ID, which identify the leaderboard it's ranked
+    /// on within its category and level.
+    pub fn subcategory_values(&self) -> BTreeMap<u64, u64> {
+        self.database
+            .variables_by_game_id(*self.game_id())
+            .into_iter()
+            .filter(|variable| {
+                *variable.is_subcategory()
+                    && variable.applies_to(*self.category_id(), *self.level_id())
+            })
+            .filter_map(|variable| {
+                self.value_id(&variable)
+                    .map(|value_id| (*variable.id(), value_id))
+            })
+            .collect()
+    }
+
+    /// Returns the user who verified this run, if we have them.
+    pub fn examiner(&self) -> Option<Linked<User>> {
+        self.examiner_id()
+            .and_then(|examiner_id| self.database.user_by_id(examiner_id))
+    }
+
     fn validate(&self) -> Result<(), IntegrityErrors> {
-        let mut errors = Vec::new();
+        let source: AnyModel = (*self.item).clone().into();
+        let mut errors = run_reference_errors(&self.database, &self.item, &source);
 
-        if self.database.game_by_id(*self.game_id()).is_none() {
-            errors.push(IntegrityError::ForeignKeyMissing {
-                target_type:       "game",
-                target_id:         *self.game_id(),
-                foreign_key_field: "game_id",
-                source:            (*self.item).clone().into(),
-            });
-        } else {
-            let game = self.game();
-            let primary_timing = game.primary_timing();
-            let times = self.times_ms();
-            if times.get(primary_timing).is_none() {
+        if let Some(game) = self.database.game_by_id(*self.game_id()) {
+            if self.times_ms().get(game.primary_timing()).is_none() {
                 errors.push(IntegrityError::MissingPrimaryTiming((**self).clone()))
             }
         }
 
-        if self
-            .database
+        if let Err(validation_errors) = self.item.validate() {
+            errors.push(IntegrityError::CheckFailed {
+                errors: validation_errors,
+                source,
+            });
+        }
+
+        IntegrityErrors::try_from(errors)
+    }
+}
+
+impl Linked<UnverifiedRun> {
+    /// Returns the Linked<Game> for this UnverifiedRun.
+    pub fn game(&self) -> Linked<Game> {
+        self.database
             .clone()
-            .category_by_id(*self.category_id())
-            .is_none()
-        {
+            .game_by_id(*self.run.game_id())
+            .expect(DATABASE_INTEGRITY)
+    }
+
+    /// Returns the Linked<Category> for this UnverifiedRun.
+    pub fn category(&self) -> Linked<Category> {
+        self.database
+            .clone()
+            .category_by_id(*self.run.category_id())
+            .expect(DATABASE_INTEGRITY)
+    }
+
+    /// Returns the user who rejected this run, if it was rejected and we have
+    /// them.
+    pub fn examiner(&self) -> Option<Linked<User>> {
+        self.run
+            .examiner_id()
+            .and_then(|examiner_id| self.database.user_by_id(examiner_id))
+    }
+
+    fn validate(&self) -> Result<(), IntegrityErrors> {
+        let source: AnyModel = (*self.item).clone().into();
+        let mut errors = run_reference_errors(&self.database, &self.run, &source);
+
+        if let Err(validation_errors) = self.item.validate() {
+            errors.push(IntegrityError::CheckFailed {
+                errors: validation_errors,
+                source,
+            });
+        }
+
+        IntegrityErrors::try_from(errors)
+    }
+}
+
+/// Checks the foreign keys of a verified or unverified run, reporting any
+/// missing targets against the `source` record that contains it.
+fn run_reference_errors(
+    database: &Arc<Database>,
+    run: &Run,
+    source: &AnyModel,
+) -> Vec<IntegrityError> {
+    let mut errors = Vec::new();
+
+    if database.game_by_id(*run.game_id()).is_none() {
+        errors.push(IntegrityError::ForeignKeyMissing {
+            target_type:       "game",
+            target_id:         *run.game_id(),
+            foreign_key_field: "game_id",
+            source:            source.clone(),
+        });
+    }
+
+    if database.category_by_id(*run.category_id()).is_none() {
+        errors.push(IntegrityError::ForeignKeyMissing {
+            target_type:       "category",
+            target_id:         *run.category_id(),
+            foreign_key_field: "category_id",
+            source:            source.clone(),
+        });
+    }
+
+    if let Some(level_id) = run.level_id() {
+        if database.level_by_id(*level_id).is_none() {
             errors.push(IntegrityError::ForeignKeyMissing {
-                target_type:       "category",
-                target_id:         *self.category_id(),
-                foreign_key_field: "category_id",
-                source:            (*self.item).clone().into(),
+                target_type:       "level",
+                target_id:         *level_id,
+                foreign_key_field: "level_id",
+                source:            source.clone(),
             });
         }
+    }
 
-        if let Some(level_id) = self.level_id() {
-            if self.database.level_by_id(*level_id).is_none() {
+    for player in run.players() {
+        if let RunPlayer::UserId(user_id) = player {
+            if database.user_by_id(*user_id).is_none() {
                 errors.push(IntegrityError::ForeignKeyMissing {
-                    target_type:       "level",
-                    target_id:         *level_id,
-                    foreign_key_field: "level_id",
-                    source:            (*self.item).clone().into(),
+                    target_type:       "user",
+                    target_id:         *user_id,
+                    foreign_key_field: "players[…].0",
+                    source:            source.clone(),
                 });
             }
         }
+    }
 
-        for player in self.players() {
-            if let RunPlayer::UserId(user_id) = player {
-                if self.database.user_by_id(*user_id).is_none() {
-                    errors.push(IntegrityError::ForeignKeyMissing {
-                        target_type:       "user",
-                        target_id:         *user_id,
-                        foreign_key_field: "players[…].0",
-                        source:            (*self.item).clone().into(),
-                    });
-                }
-            }
+    if let Some(platform_id) = run.platform_id() {
+        if database.platform_by_id(*platform_id).is_none() {
+            errors.push(IntegrityError::ForeignKeyMissing {
+                target_type:       "platform",
+                target_id:         *platform_id,
+                foreign_key_field: "platform_id",
+                source:            source.clone(),
+            });
         }
+    }
 
-        if let Err(validation_errors) = self.item.validate() {
-            errors.push(IntegrityError::CheckFailed {
-                errors: validation_errors,
-                source: (*self.item).clone().into(),
+    if let Some(region_id) = run.region_id() {
+        if database.region_by_id(*region_id).is_none() {
+            errors.push(IntegrityError::ForeignKeyMissing {
+                target_type:       "region",
+                target_id:         *region_id,
+                foreign_key_field: "region_id",
+                source:            source.clone(),
             });
         }
+    }
 
-        IntegrityErrors::try_from(errors)
+    for (variable_id, value_id) in run.values() {
+        match database.variable_by_id(*variable_id) {
+            None => errors.push(IntegrityError::ForeignKeyMissing {
+                target_type:       "variable",
+                target_id:         *variable_id,
+                foreign_key_field: "values[…].0",
+                source:            source.clone(),
+            }),
+            Some(variable) =>
+                if variable.value_by_id(*value_id).is_none() {
+                    errors.push(IntegrityError::ForeignKeyMissing {
+                        target_type:       "variable value",
+                        target_id:         *value_id,
+                        foreign_key_field: "values[…].1",
+                        source:            source.clone(),
+                    });
+                },
+        }
     }
+
+    errors
 }
 
 impl Linked<User> {
+    /// Returns a Vec of all the verified Runs this User participated in.
+    pub fn runs(&self) -> Vec<Linked<Run>> {
+        self.database.runs_by_user_id(*self.id())
+    }
+
+    /// Returns a Vec of the Games this User moderates, sorted by name.
+    pub fn moderated_games(&self) -> Vec<Linked<Game>> {
+        self.database.games_by_moderator_id(*self.id())
+    }
+
     fn validate(&self) -> Result<(), IntegrityErrors> {
         let mut errors = Vec::new();
 
@@ -650,6 +1461,120 @@ impl Linked<Game> {
             .expect(DATABASE_INTEGRITY)
     }
 
+    /// Returns a Vec of the pending and rejected Runs for this Game.
+    pub fn unverified_runs(&self) -> Vec<Linked<UnverifiedRun>> {
+        self.database.unverified_runs_by_game_id(*self.id())
+    }
+
+    /// Returns a Vec of the moderators of this Game that we have users for,
+    /// with the kind of moderator each is.
+    pub fn moderators(&self) -> Vec<(Linked<User>, ModeratorKind)> {
+        self.item
+            .moderators()
+            .iter()
+            .filter_map(|moderator| {
+                let user = self.database.user_by_id(*moderator.user_id())?;
+                Some((user, *moderator.kind()))
+            })
+            .collect()
+    }
+
+    /// Returns a Vec of all of the Categories for this Game, sorted by name.
+    pub fn categories(&self) -> Vec<Linked<Category>> {
+        self.database.categories_by_game_id(*self.id())
+    }
+
+    /// Returns a Vec of all of the Levels for this Game, sorted by name.
+    pub fn levels(&self) -> Vec<Linked<Level>> {
+        self.database.levels_by_game_id(*self.id())
+    }
+
+    /// Returns a Vec of all of the Variables for this Game, sorted by name.
+    pub fn variables(&self) -> Vec<Linked<Variable>> {
+        self.database.variables_by_game_id(*self.id())
+    }
+
+    /// Returns a Vec of the Platforms this Game can be run on.
+    pub fn platforms(&self) -> Vec<Linked<Platform>> {
+        self.platform_ids()
+            .iter()
+            .map(|platform_id| {
+                self.database
+                    .clone()
+                    .platform_by_id(*platform_id)
+                    .expect(DATABASE_INTEGRITY)
+            })
+            .collect()
+    }
+
+    /// Returns a Vec of the Regions this Game was released in.
+    pub fn regions(&self) -> Vec<Linked<Region>> {
+        self.region_ids()
+            .iter()
+            .map(|region_id| {
+                self.database
+                    .clone()
+                    .region_by_id(*region_id)
+                    .expect(DATABASE_INTEGRITY)
+            })
+            .collect()
+    }
+
+    /// Returns the Linked<GameRelation> for this Game, if it has any
+    /// relationships.
+    pub fn relation(&self) -> Option<Linked<GameRelation>> {
+        self.database.game_relation_by_game_id(*self.id())
+    }
+
+    /// Returns the Game this one is derived from, if any.
+    pub fn base_game(&self) -> Option<Linked<Game>> {
+        self.relation()?.base_game()
+    }
+
+    /// Returns a Vec of the Games derived from this one, including ROM hacks,
+    /// sorted by name.
+    pub fn derived_games(&self) -> Vec<Linked<Game>> {
+        self.database.derived_games_by_base_game_id(*self.id())
+    }
+
+    /// Returns a Vec of the ROM hacks of this Game, sorted by name.
+    pub fn romhacks(&self) -> Vec<Linked<Game>> {
+        self.derived_games()
+            .into_iter()
+            .filter(|game| *game.romhack())
+            .collect()
+    }
+
+    /// Returns a Vec of every Game in any of the series this one belongs to,
+    /// including this one, sorted by name.
+    pub fn series(&self) -> Vec<Linked<Game>> {
+        let series_ids = match self.relation() {
+            Some(relation) => relation.series_ids.clone(),
+            None => return vec![],
+        };
+        let mut games = series_ids
+            .iter()
+            .flat_map(|series_id| self.database.games_by_series_id(*series_id))
+            .collect::<Vec<_>>();
+        games.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+        games.dedup_by_key(|game| game.id);
+        games
+    }
+
+    /// Returns a Vec of this Game's tags of the given kind.
+    pub fn tags(&self, kind: GameTagKind) -> Vec<Linked<GameTag>> {
+        self.tag_ids()
+            .iter()
+            .map(|tag_id| {
+                self.database
+                    .clone()
+                    .game_tag_by_id(*tag_id)
+                    .expect(DATABASE_INTEGRITY)
+            })
+            .filter(|tag| *tag.kind() == kind)
+            .collect()
+    }
+
     pub fn per_game_category_by_slug(&self, slug: &str) -> Option<Linked<Category>> {
         self.database
             .clone()
@@ -678,10 +1603,96 @@ impl Linked<Game> {
             });
         }
 
+        for platform_id in self.platform_ids() {
+            if self.database.platform_by_id(*platform_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "platform",
+                    target_id:         *platform_id,
+                    foreign_key_field: "platform_ids[…]",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
+        for region_id in self.region_ids() {
+            if self.database.region_by_id(*region_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "region",
+                    target_id:         *region_id,
+                    foreign_key_field: "region_ids[…]",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
+        for tag_id in self.tag_ids() {
+            if self.database.game_tag_by_id(*tag_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "game tag",
+                    target_id:         *tag_id,
+                    foreign_key_field: "tag_ids[…]",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
+        IntegrityErrors::try_from(errors)
+    }
+}
+
+impl Linked<GameRelation> {
+    /// Returns the Linked<Game> these relationships belong to.
+    pub fn game(&self) -> Linked<Game> {
+        self.database
+            .clone()
+            .game_by_id(*self.game_id())
+            .expect(DATABASE_INTEGRITY)
+    }
+
+    /// Returns the Linked<Game> this game is derived from, if any.
+    pub fn base_game(&self) -> Option<Linked<Game>> {
+        self.base_game_id().map(|base_game_id| {
+            self.database
+                .clone()
+                .game_by_id(base_game_id)
+                .expect(DATABASE_INTEGRITY)
+        })
+    }
+
+    fn validate(&self) -> Result<(), IntegrityErrors> {
+        let mut errors = Vec::new();
+
+        if self.database.game_by_id(*self.game_id()).is_none() {
+            errors.push(IntegrityError::ForeignKeyMissing {
+                target_type:       "game",
+                target_id:         *self.game_id(),
+                foreign_key_field: "game_id",
+                source:            (*self.item).clone().into(),
+            });
+        }
+
+        if let Some(base_game_id) = self.base_game_id() {
+            if self.database.game_by_id(*base_game_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "game",
+                    target_id:         *base_game_id,
+                    foreign_key_field: "base_game_id",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
         IntegrityErrors::try_from(errors)
     }
 }
 
+impl Linked<GameTag> {
+    /// Returns a Vec of the Games with this tag, sorted by name.
+    pub fn games(&self) -> Vec<Linked<Game>> {
+        self.database.games_by_tag_id(*self.id())
+    }
+}
+
 impl Linked<Level> {
     /// Returns the Linked<Game> for this Level.
     pub fn game(&self) -> Linked<Game> {
@@ -723,27 +1734,47 @@ impl Linked<Category> {
             .expect(DATABASE_INTEGRITY)
     }
 
+    /// Returns a Vec of all the verified Runs in this Category, for every level.
     pub fn runs(&self) -> Vec<Linked<Run>> {
+        self.database.query_runs().category(self).to_vec()
+    }
+
+    /// Returns a Vec of the verified Runs in this Category for the given Level,
+    /// or for the full game if None.
+    pub fn runs_for(&self, level: Option<&Level>) -> Vec<Linked<Run>> {
         self.database
-            .clone()
-            .runs_by_game_id(*self.game_id())
-            .expect(DATABASE_INTEGRITY)
-            .iter()
-            .filter(|r| r.category_id() == self.id())
-            .cloned()
-            .collect()
+            .runs_by_category_id_and_level_id(*self.id(), level.map(|level| *level.id()))
     }
 
     pub fn full_runs(&self) -> Vec<Linked<Run>> {
-        let mut runs = self.runs();
-        runs.retain(|run| run.level_id().is_none());
-        runs
+        self.runs_for(None)
     }
 
     pub fn level_runs(&self, level: &Level) -> Vec<Linked<Run>> {
-        let mut runs = self.runs();
-        runs.retain(|run| *run.level_id() == Some(*level.id()));
-        runs
+        self.runs_for(Some(level))
+    }
+
+    /// Returns a Vec of the Variables that apply to runs in this Category for
+    /// the given Level, or for the full game if None, sorted by name.
+    pub fn variables_for(&self, level: Option<&Level>) -> Vec<Linked<Variable>> {
+        let level_id = level.map(|level| *level.id());
+        self.game()
+            .variables()
+            .into_iter()
+            .filter(|variable| variable.applies_to(*self.id(), level_id))
+            .collect()
+    }
+
+    /// Finds a Variable that applies to runs in this Category for the given
+    /// Level by slug.
+    pub fn variable_by_slug(
+        &self,
+        level: Option<&Level>,
+        slug: &str,
+    ) -> Option<Linked<Variable>> {
+        self.variables_for(level)
+            .into_iter()
+            .find(|variable| variable.slug() == slug)
     }
 
     fn validate(&self) -> Result<(), IntegrityErrors> {
@@ -768,3 +1799,79 @@ impl Linked<Category> {
         IntegrityErrors::try_from(errors)
     }
 }
+
+impl Linked<Variable> {
+    /// Returns the Linked<Game> for this Variable.
+    pub fn game(&self) -> Linked<Game> {
+        self.database
+            .clone()
+            .game_by_id(*self.game_id())
+            .expect(DATABASE_INTEGRITY)
+    }
+
+    /// Returns Some(Linked<Category>) if this Variable only applies to one
+    /// category, or None if it applies to all of them.
+    pub fn category(&self) -> Option<Linked<Category>> {
+        self.category_id().map(|category_id| {
+            self.database
+                .clone()
+                .category_by_id(category_id)
+                .expect(DATABASE_INTEGRITY)
+        })
+    }
+
+    fn validate(&self) -> Result<(), IntegrityErrors> {
+        let mut errors = Vec::new();
+
+        if let Err(validation_errors) = self.item.validate() {
+            errors.push(IntegrityError::CheckFailed {
+                errors: validation_errors,
+                source: (*self.item).clone().into(),
+            });
+        }
+
+        if self.database.game_by_id(*self.game_id()).is_none() {
+            errors.push(IntegrityError::ForeignKeyMissing {
+                target_type:       "game",
+                target_id:         *self.game_id(),
+                foreign_key_field: "game_id",
+                source:            (*self.item).clone().into(),
+            });
+        }
+
+        if let Some(category_id) = self.category_id() {
+            if self.database.category_by_id(*category_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "category",
+                    target_id:         *category_id,
+                    foreign_key_field: "category_id",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
+        if let VariableScope::SingleLevel(level_id) = self.scope() {
+            if self.database.level_by_id(*level_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "level",
+                    target_id:         *level_id,
+                    foreign_key_field: "scope.0",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
+        if let Some(value_id) = self.default_value_id() {
+            if self.value_by_id(*value_id).is_none() {
+                errors.push(IntegrityError::ForeignKeyMissing {
+                    target_type:       "variable value",
+                    target_id:         *value_id,
+                    foreign_key_field: "default_value_id",
+                    source:            (*self.item).clone().into(),
+                });
+            }
+        }
+
+        IntegrityErrors::try_from(errors)
+    }
+}
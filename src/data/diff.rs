@@ -0,0 +1,375 @@
+//! Compares two versions of [Tables] to produce a changelog of the records
+//! that were added, removed, or modified between them, and of any new records
+//! (in the speedrunning sense) that were set.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Display,
+    sync::Arc,
+};
+
+use itertools::{EitherOrBoth, Itertools};
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::data::{
+    database::{Database, IntegrityErrors, Tables},
+    leaderboard::{leaderboard, SystemFilter},
+    models::{AnyModel, Model},
+    types::*,
+};
+
+/// A change to a single record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Change {
+    Added(AnyModel),
+    Removed(AnyModel),
+    Modified {
+        /// The record as it is after the change.
+        record: AnyModel,
+        fields: Vec<FieldChange>,
+    },
+}
+
+impl Change {
+    /// The record as it is after this change, or as it was before it was
+    /// removed.
+    pub fn record(&self) -> &AnyModel {
+        match self {
+            Change::Added(record) => record,
+            Change::Removed(record) => record,
+            Change::Modified { record, .. } => record,
+        }
+    }
+}
+
+/// A change to a single top-level field of a record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field:  String,
+    pub before: JsonValue,
+    pub after:  JsonValue,
+}
+
+/// A run that was added or modified and is now the first place run on its
+/// leaderboard, faster than the one before it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewRecord {
+    pub run:              Run,
+    pub time_ms:          u64,
+    /// The first place run on the leaderboard before, if there was one.
+    pub previous:         Option<Run>,
+    pub previous_time_ms: Option<u64>,
+}
+
+/// Every change between two versions of [Tables].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Changelog {
+    pub changes:     Vec<Change>,
+    pub new_records: Vec<NewRecord>,
+}
+
+impl Display for Changelog {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} changes, {} new records:",
+            self.changes.len(),
+            self.new_records.len()
+        )?;
+
+        let mut counts = BTreeMap::<(&str, &str), usize>::new();
+        for change in self.changes.iter() {
+            let action = match change {
+                Change::Added(_) => "added",
+                Change::Removed(_) => "removed",
+                Change::Modified { .. } => "modified",
+            };
+            *counts
+                .entry((action, change.record().table_name()))
+                .or_default() += 1;
+        }
+        for ((action, type_name), count) in counts {
+            writeln!(f, "{:8} {:6} {}", action, count, type_name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares two versions of the tables, which must both be valid.
+///
+/// Changes are grouped by table (games, categories, levels, variables,
+/// platforms, regions, game tags, game relations, users, runs, then unverified
+/// runs) and sorted by ID within each. A run that's verified is removed from
+/// the unverified runs and added to the runs.
+pub fn diff(before: Arc<Tables>, after: Arc<Tables>) -> Result<Changelog, IntegrityErrors> {
+    let mut changes = Vec::new();
+    diff_table(before.games(), after.games(), &mut changes);
+    diff_table(before.categories(), after.categories(), &mut changes);
+    diff_table(before.levels(), after.levels(), &mut changes);
+    diff_table(before.variables(), after.variables(), &mut changes);
+    diff_table(before.platforms(), after.platforms(), &mut changes);
+    diff_table(before.regions(), after.regions(), &mut changes);
+    diff_table(before.game_tags(), after.game_tags(), &mut changes);
+    diff_table(
+        before.game_relations(),
+        after.game_relations(),
+        &mut changes,
+    );
+    diff_table(before.users(), after.users(), &mut changes);
+    diff_table(before.runs(), after.runs(), &mut changes);
+    diff_table(
+        before.unverified_runs(),
+        after.unverified_runs(),
+        &mut changes,
+    );
+
+    let before = Database::new(before)?;
+    let after = Database::new(after)?;
+    let new_records = new_records(&before, &after, &changes);
+
+    Ok(Changelog {
+        changes,
+        new_records,
+    })
+}
+
+fn diff_table<T: Model>(
+    before: &BTreeMap<u64, Arc<T>>,
+    after: &BTreeMap<u64, Arc<T>>,
+    changes: &mut Vec<Change>,
+) {
+    for pair in before
+        .iter()
+        .merge_join_by(after.iter(), |(a, _), (b, _)| a.cmp(b))
+    {
+        match pair {
+            EitherOrBoth::Left((_, record)) =>
+                changes.push(Change::Removed(T::clone(record).into())),
+            EitherOrBoth::Right((_, record)) =>
+                changes.push(Change::Added(T::clone(record).into())),
+            EitherOrBoth::Both((_, old), (_, new)) =>
+                if old != new {
+                    changes.push(Change::Modified {
+                        fields: changed_fields(&**old, &**new),
+                        record: T::clone(new).into(),
+                    })
+                },
+        }
+    }
+}
+
+/// Lists the top-level fields whose serialized values differ between two
+/// versions of a record.
+fn changed_fields<T: Serialize>(before: &T, after: &T) -> Vec<FieldChange> {
+    let before = serde_json::to_value(before).expect("models serialize to JSON");
+    let after = serde_json::to_value(after).expect("models serialize to JSON");
+
+    match (before, after) {
+        (JsonValue::Object(before), JsonValue::Object(mut after)) => {
+            let mut fields = Vec::new();
+            for (field, before) in before {
+                let after = after.remove(&field).unwrap_or(JsonValue::Null);
+                if before != after {
+                    fields.push(FieldChange {
+                        field,
+                        before,
+                        after,
+                    });
+                }
+            }
+            for (field, after) in after {
+                fields.push(FieldChange {
+                    field,
+                    before: JsonValue::Null,
+                    after,
+                });
+            }
+            fields
+        }
+        (before, after) => vec![FieldChange {
+            field: String::new(),
+            before,
+            after,
+        }],
+    }
+}
+
+/// Finds added or modified runs that are now in first place on their
+/// leaderboards, and are faster than the previous first place run, if there
+/// was one. Runs by banned users are left out, as they are on leaderboards.
+fn new_records(
+    before: &Arc<Database>,
+    after: &Arc<Database>,
+    changes: &[Change],
+) -> Vec<NewRecord> {
+    let mut changed_run_ids = HashSet::new();
+    let mut boards = BTreeSet::new();
+    for change in changes {
+        match change {
+            Change::Added(AnyModel::Run(run))
+            | Change::Modified {
+                record: AnyModel::Run(run),
+                ..
+            } => {
+                changed_run_ids.insert(*run.id());
+                let values = after
+                    .run_by_id(*run.id())
+                    .expect("changed run is in the new tables")
+                    .subcategory_values();
+                boards.insert((*run.category_id(), *run.level_id(), values));
+            }
+            _ => {}
+        }
+    }
+
+    let mut records = Vec::new();
+    for (category_id, level_id, values) in boards {
+        let category = after
+            .category_by_id(category_id)
+            .expect("changed run's category is in the new tables");
+        let level = level_id.map(|level_id| {
+            after
+                .level_by_id(level_id)
+                .expect("changed run's level is in the new tables")
+        });
+        let timing = category.game().primary_timing().clone();
+        let board = |database: &Arc<Database>| {
+            let runs = database
+                .query_runs()
+                .category(&category)
+                .level(level.as_deref())
+                .without_banned_users()
+                .to_vec();
+            leaderboard(
+                &runs,
+                &values,
+                &SystemFilter::default(),
+                &timing,
+                false,
+                None,
+            )
+        };
+
+        let previous = board(before).into_iter().next();
+        let current = board(after);
+
+        for ranked in current.iter().take_while(|ranked| *ranked.tied_rank() == 1) {
+            let run = ranked.run();
+            if !changed_run_ids.contains(run.id()) {
+                continue
+            }
+            if let Some(previous) = &previous {
+                if previous.run().id() == run.id() || ranked.time_ms() >= previous.time_ms()
+                {
+                    continue
+                }
+            }
+
+            records.push(NewRecord {
+                run:              Run::clone(run),
+                time_ms:          *ranked.time_ms(),
+                previous:         previous
+                    .as_ref()
+                    .map(|previous| Run::clone(previous.run())),
+                previous_time_ms: previous.as_ref().map(|previous| *previous.time_ms()),
+            });
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{self, category, game, run};
+
+    fn user(id: u64, name: &str) -> User {
+        User {
+            slug: name.to_lowercase(),
+            name: name.to_string(),
+            ..fixtures::user(id)
+        }
+    }
+
+    fn tables(runs: Vec<Run>, users: Vec<User>) -> Arc<Tables> {
+        Arc::new(
+            fixtures::tables()
+                .runs(runs)
+                .users(users)
+                .games(vec![game(1)])
+                .categories(vec![category(2, 1)])
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = tables(
+            vec![run(10, 5, 3000), run(11, 6, 2000)],
+            vec![user(5, "Alice"), user(6, "Bob")],
+        );
+        let after = tables(
+            vec![run(11, 6, 2000), run(12, 5, 1500)],
+            vec![user(5, "Alicia"), user(6, "Bob")],
+        );
+
+        let changelog = diff(before, after).expect("valid tables");
+
+        assert_eq!(changelog.changes.len(), 3);
+        match &changelog.changes[0] {
+            Change::Modified {
+                record: AnyModel::User(user),
+                fields,
+            } => {
+                assert_eq!(*user.id(), 5);
+                assert_eq!(
+                    fields.iter().map(|f| f.field.as_str()).collect::<Vec<_>>(),
+                    vec!["name", "slug"]
+                );
+            }
+            other => panic!("unexpected change: {:?}", other),
+        }
+        assert_eq!(
+            changelog.changes[1],
+            Change::Removed(run(10, 5, 3000).into())
+        );
+        assert_eq!(changelog.changes[2], Change::Added(run(12, 5, 1500).into()));
+
+        assert_eq!(changelog.new_records.len(), 1);
+        let record = &changelog.new_records[0];
+        assert_eq!(*record.run.id(), 12);
+        assert_eq!(record.time_ms, 1500);
+        assert_eq!(record.previous.as_ref().map(|run| *run.id()), Some(11));
+        assert_eq!(record.previous_time_ms, Some(2000));
+    }
+
+    #[test]
+    fn test_new_records_exclude_ties_and_banned_users() {
+        let banned = User {
+            role: UserRole::Banned,
+            ..user(7, "Mallory")
+        };
+        let before = tables(
+            vec![run(10, 5, 2000), run(11, 6, 2000)],
+            vec![user(5, "Alice"), user(6, "Bob"), banned.clone()],
+        );
+        let after = tables(
+            vec![
+                run(10, 5, 2000),
+                Run {
+                    comment: Some("retimed".to_string()),
+                    ..run(11, 6, 2000)
+                },
+                run(12, 7, 1000),
+            ],
+            vec![user(5, "Alice"), user(6, "Bob"), banned],
+        );
+
+        let changelog = diff(before, after).expect("valid tables");
+
+        assert_eq!(changelog.changes.len(), 2);
+        assert_eq!(changelog.new_records, vec![]);
+    }
+}
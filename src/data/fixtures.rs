@@ -0,0 +1,213 @@
+//! Records for tests, with placeholder values for every field a test doesn't
+//! set itself with struct update syntax, like
+//! `Run { level_id: Some(3), ..run(10, 5, 100) }`.
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::data::{
+    database::{Database, Tables},
+    types::*,
+};
+
+/// A full-game run of category 2 of game 1 by the given user, with only a
+/// real-time time.
+pub fn run(id: u64, user_id: u64, rta_ms: u64) -> Run {
+    Run {
+        game_id: 1,
+        category_id: 2,
+        level_id: None,
+        id,
+        created: None,
+        date: None,
+        times_ms: RunTimesMs {
+            igt:    None,
+            rta:    Some(rta_ms),
+            rta_nl: None,
+        },
+        players: vec![RunPlayer::UserId(user_id)],
+        values: BTreeMap::new(),
+        videos: vec![],
+        comment: None,
+        platform_id: None,
+        region_id: None,
+        emulated: false,
+        examiner_id: None,
+        verified: None,
+    }
+}
+
+pub fn user(id: u64) -> User {
+    User {
+        id,
+        created: None,
+        slug: format!("user-{}", id),
+        name: format!("User {}", id),
+        location: None,
+        links: UserLinks::default(),
+        role: UserRole::User,
+        name_style: None,
+    }
+}
+
+/// A game timed by real time.
+pub fn game(id: u64) -> Game {
+    Game {
+        id,
+        created: None,
+        slug: format!("game-{}", id),
+        src_slug: format!("game-{}", id),
+        name: format!("Game {}", id),
+        primary_timing: TimingMethod::RTA,
+        platform_ids: vec![],
+        region_ids: vec![],
+        release_date: None,
+        romhack: false,
+        tag_ids: vec![],
+        assets: GameAssets::default(),
+        moderators: vec![],
+    }
+}
+
+/// A full-game category, named Any%.
+pub fn category(id: u64, game_id: u64) -> Category {
+    Category {
+        game_id,
+        slug: "any".to_string(),
+        name: "Any%".to_string(),
+        id,
+        per: CategoryType::PerGame,
+        rules: String::new(),
+    }
+}
+
+pub fn level(id: u64, game_id: u64) -> Level {
+    Level {
+        game_id,
+        id,
+        slug: format!("level-{}", id),
+        name: format!("Level {}", id),
+        rules: String::new(),
+    }
+}
+
+/// A subcategory variable of the given category of game 1 that applies to
+/// every run in it, without a default value.
+pub fn variable(id: u64, category_id: u64, values: Vec<VariableValue>) -> Variable {
+    Variable {
+        game_id: 1,
+        id,
+        category_id: Some(category_id),
+        scope: VariableScope::Global,
+        slug: format!("variable-{}", id),
+        name: format!("Variable {}", id),
+        is_subcategory: true,
+        mandatory: false,
+        obsoletes: true,
+        default_value_id: None,
+        values,
+    }
+}
+
+pub fn value(id: u64, label: &str) -> VariableValue {
+    VariableValue {
+        id,
+        slug: label.to_lowercase(),
+        label: label.to_string(),
+        rules: String::new(),
+        miscellaneous: false,
+    }
+}
+
+/// Returns a builder of [Tables], which are empty except for the records that
+/// are given.
+pub fn tables() -> TablesBuilder {
+    TablesBuilder::default()
+}
+
+#[derive(Debug, Default)]
+pub struct TablesBuilder {
+    runs:            Vec<Run>,
+    users:           Vec<User>,
+    games:           Vec<Game>,
+    categories:      Vec<Category>,
+    levels:          Vec<Level>,
+    variables:       Vec<Variable>,
+    platforms:       Vec<Platform>,
+    regions:         Vec<Region>,
+    game_tags:       Vec<GameTag>,
+    game_relations:  Vec<GameRelation>,
+    unverified_runs: Vec<UnverifiedRun>,
+}
+
+impl TablesBuilder {
+    pub fn runs(mut self, runs: Vec<Run>) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    pub fn users(mut self, users: Vec<User>) -> Self {
+        self.users = users;
+        self
+    }
+
+    pub fn games(mut self, games: Vec<Game>) -> Self {
+        self.games = games;
+        self
+    }
+
+    pub fn categories(mut self, categories: Vec<Category>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    pub fn levels(mut self, levels: Vec<Level>) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn variables(mut self, variables: Vec<Variable>) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    pub fn platforms(mut self, platforms: Vec<Platform>) -> Self {
+        self.platforms = platforms;
+        self
+    }
+
+    pub fn game_tags(mut self, game_tags: Vec<GameTag>) -> Self {
+        self.game_tags = game_tags;
+        self
+    }
+
+    pub fn game_relations(mut self, game_relations: Vec<GameRelation>) -> Self {
+        self.game_relations = game_relations;
+        self
+    }
+
+    pub fn unverified_runs(mut self, unverified_runs: Vec<UnverifiedRun>) -> Self {
+        self.unverified_runs = unverified_runs;
+        self
+    }
+
+    pub fn build(self) -> Tables {
+        Tables::new(
+            self.runs,
+            self.users,
+            self.games,
+            self.categories,
+            self.levels,
+            self.variables,
+            self.platforms,
+            self.regions,
+            self.game_tags,
+            self.game_relations,
+            self.unverified_runs,
+        )
+    }
+
+    /// Builds the tables and links them into a [Database], panicking if they
+    /// fail integrity checks.
+    pub fn database(self) -> Arc<Database> {
+        Database::new(Arc::new(self.build())).expect("valid database")
+    }
+}
@@ -7,6 +7,7 @@ pub enum NodeType {
     Run,
     Category,
     Level,
+    Platform,
 }
 
 use NodeType::*;
@@ -22,7 +23,7 @@ pub fn global_id(id: u64, node_type: NodeType) -> ID {
 
      speedrun.com IDs are 8 base-36 digits, which means they require
      log2(36**8) = ~41.3 bits, giving us 64 - 42 = 22 bits clear to work with.
-     We only really need a few to identify our five node types, but since
+     We only really need a few to identify our six node types, but since
      22 bits / 6 bits per base-64 character = ~3.6, we can instead use 18 of
      them to pick three meaningful prefix character in the encoded value. The
      last four bits are distinct, so that they identify the type even without
@@ -36,6 +37,7 @@ pub fn global_id(id: u64, node_type: NodeType) -> ID {
         Run => (0b1010_1110, 0b1110_1001, 0b1110_1000),  // run
         Category => (0b0111_0001, 0b1010_1011, 0b0111_1000), // cat
         Level => (0b1001_0110, 0b1111_1001, 0b0100_1000), // lvl
+        Platform => (0b1010_0110, 0b0101_1011, 0b0101_0100), // plt
     };
 
     bytes[0] = a;
@@ -57,6 +59,7 @@ pub fn parse_global_id(
         0b1010_1110 => Run,
         0b0111_0001 => Category,
         0b1001_0110 => Level,
+        0b1010_0110 => Platform,
         _ => panic!("high byte didn't match expected tag values"),
     };
 
@@ -96,6 +99,9 @@ fn test_round_trip_global_ids() {
         (zeroes, NodeType::Category, "cat4AAAAAAA"),
         (ones, NodeType::Category, "cat6kNdA__8"),
         (alphabet, NodeType::Category, "cat4vDR7UsE"),
+        (zeroes, NodeType::Platform, "pltUAAAAAAA"),
+        (ones, NodeType::Platform, "pltWkNdA__8"),
+        (alphabet, NodeType::Platform, "pltUvDR7UsE"),
     ];
 
     for (id, node_type, global) in &cases {
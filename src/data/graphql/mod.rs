@@ -1,25 +1,25 @@
-#![warn(
-    clippy::option_unwrap_used,
-    clippy::result_unwrap_used
-)]
+#![warn(clippy::option_unwrap_used, clippy::result_unwrap_used)]
 
-use std::{convert::TryFrom, sync::Arc};
+use std::{collections::BTreeMap, convert::TryFrom, sync::Arc};
 
-use itertools::Itertools;
+use chrono::{Datelike, NaiveDate};
 #[allow(unused)]
 use juniper::{
     graphql_interface, graphql_object, graphql_scalar, graphql_union, graphql_value,
     object, GraphQLEnum, GraphQLInputObject, GraphQLObject, GraphQLScalarValue,
     ScalarValue,
 };
-use juniper::{Executor, ID};
+use juniper::{Executor, FieldResult, ID};
 use juniper_from_schema::graphql_schema_from_file;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
 
 use crate::{
     data::{
         database::{Database, Linked as DbLinked},
         graphql::global_id::{global_id, parse_global_id, NodeType},
-        leaderboard, progression, types as db,
+        leaderboard, moderation, personal_bests, progression, search,
+        shards::ShardedDatabase,
+        sum_of_best, types as db,
     },
     utils::{base36, src_slugify},
 };
@@ -34,7 +34,75 @@ pub fn schema() -> Schema {
 
 #[derive(Debug, Clone)]
 pub struct Context {
+    /// The full database, or the index of the sharded database, which has
+    /// every record except runs.
     pub database: Arc<Database>,
+    pub shards:   Option<Arc<ShardedDatabase>>,
+}
+
+impl Context {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            shards: None,
+        }
+    }
+
+    pub fn sharded(shards: Arc<ShardedDatabase>) -> Self {
+        Self {
+            database: shards.index().clone(),
+            shards:   Some(shards),
+        }
+    }
+
+    /// Returns the database with the given game's runs: the full database, or
+    /// the game's shard (loading it if necessary).
+    fn game_database(&self, game_id: u64) -> Option<Arc<Database>> {
+        match &self.shards {
+            None => Some(self.database.clone()),
+            Some(shards) => match shards.game_database(game_id) {
+                Ok(database) => database,
+                Err(error) => {
+                    error!("Failed to load shard for game {}: {}", game_id, error);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Finds a run by ID, in the full database or its game's shard.
+    fn run_by_id(&self, run_id: u64) -> Option<DbLinked<db::Run>> {
+        match &self.shards {
+            None => self.database.run_by_id(run_id),
+            Some(shards) => match shards.run_by_id(run_id) {
+                Ok(run) => run,
+                Err(error) => {
+                    error!("Failed to load shard for run {}: {}", run_id, error);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Returns the databases with the given user's runs: the full database, or
+    /// the shards of every game they've run.
+    fn user_databases(&self, user_id: u64) -> Vec<Arc<Database>> {
+        match &self.shards {
+            None => vec![self.database.clone()],
+            Some(shards) => shards
+                .game_ids_by_user_id(user_id)
+                .iter()
+                .filter_map(|game_id| self.game_database(*game_id))
+                .collect(),
+        }
+    }
+
+    /// Returns the given game linked to the database with its runs. Records
+    /// are otherwise resolved from the (possibly sharded) index, so that a
+    /// game's shard is only loaded once one of its fields needs runs.
+    fn game_with_runs(&self, game_id: u64) -> Option<DbLinked<db::Game>> {
+        self.game_database(game_id)?.game_by_id(game_id)
+    }
 }
 
 impl juniper::Context for Context {}
@@ -48,6 +116,12 @@ pub struct Game(DbLinked<db::Game>);
 #[derive(Debug, Clone)]
 pub struct Run(DbLinked<db::Run>);
 
+#[derive(Debug, Clone)]
+pub struct RunTimes(db::RunTimesMs);
+
+#[derive(Debug, Clone)]
+pub struct RunVideo(db::RunVideo);
+
 #[derive(Debug, Clone)]
 pub struct LeaderboardRun(leaderboard::LeaderboardRun);
 
@@ -60,6 +134,18 @@ pub struct Category(DbLinked<db::Category>);
 #[derive(Debug, Clone)]
 pub struct User(DbLinked<db::User>);
 
+#[derive(Debug, Clone)]
+pub struct UserLocation(db::UserLocation);
+
+#[derive(Debug, Clone)]
+pub struct UserLinks(db::UserLinks);
+
+#[derive(Debug, Clone)]
+pub struct UserNameStyle(db::UserNameStyle);
+
+#[derive(Debug, Clone)]
+pub struct UserColor(db::UserColor);
+
 #[derive(Debug, Clone)]
 pub enum Player {
     User(User),
@@ -68,6 +154,124 @@ pub enum Player {
 
 #[derive(Debug, Clone)]
 pub struct Level(DbLinked<db::Level>);
+
+#[derive(Debug, Clone)]
+pub struct SearchHit(search::SearchHit);
+
+#[derive(Debug, Clone)]
+pub struct Variable(DbLinked<db::Variable>);
+
+#[derive(Debug, Clone)]
+pub struct VariableValue(db::VariableValue);
+
+#[derive(Debug, Clone)]
+pub struct Platform(DbLinked<db::Platform>);
+
+#[derive(Debug, Clone)]
+pub struct Region(DbLinked<db::Region>);
+
+#[derive(Debug, Clone)]
+pub struct GameTag(DbLinked<db::GameTag>);
+
+#[derive(Debug, Clone)]
+pub struct GameAsset(db::GameAsset);
+
+#[derive(Debug, Clone)]
+pub struct GameModerator(DbLinked<db::User>, db::ModeratorKind);
+
+#[derive(Debug, Clone)]
+pub struct GameModeration(moderation::ModerationStats);
+
+#[derive(Debug, Clone)]
+pub struct PersonalBest(personal_bests::PersonalBest);
+
+#[derive(Debug, Clone)]
+pub struct SumOfBest(sum_of_best::SumOfBest);
+
+#[derive(Debug, Clone)]
+pub struct LevelBest(sum_of_best::LevelBest);
+
+#[derive(Debug, Clone)]
+pub struct SumOfBestProgress(sum_of_best::SumOfBestProgress);
+
+/// A moderator's user ID and how many runs they've rejected.
+#[derive(Debug, Clone)]
+pub struct ModeratorRejections(u64, usize);
+
+/// Resolves variable and value slugs to value IDs by variable ID, or an error
+/// naming the first that doesn't exist for runs of the given category and
+/// level.
+fn variable_values(
+    category: &DbLinked<db::Category>,
+    level: Option<&db::Level>,
+    filters: Option<Vec<VariableFilter>>,
+) -> FieldResult<BTreeMap<u64, u64>> {
+    filters
+        .unwrap_or_default()
+        .iter()
+        .map(|filter| -> FieldResult<(u64, u64)> {
+            let variable = category
+                .variable_by_slug(level, &filter.variable)
+                .ok_or_else(|| {
+                    format!(
+                        "variables: no variable {:?} for this category",
+                        filter.variable
+                    )
+                })?;
+            let value = variable.value_by_slug(&filter.value).ok_or_else(|| {
+                format!(
+                    "variables: no value {:?} for variable {:?}",
+                    filter.value, filter.variable
+                )
+            })?;
+            Ok((variable.id, value.id))
+        })
+        .collect()
+}
+
+/// Parses the optional YYYY-MM-DD date argument with the given name, or
+/// returns an error naming it if it's invalid.
+fn parse_date(name: &str, date: Option<String>) -> FieldResult<Option<NaiveDate>> {
+    date.map(|date| {
+        NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|_| format!("{}: {:?} isn't a YYYY-MM-DD date", name, date).into())
+    })
+    .transpose()
+}
+
+/// Resolves platform and region slugs to a filter on runs, or an error naming
+/// the argument that doesn't exist.
+fn system_filter(
+    database: &Arc<Database>,
+    platform: Option<String>,
+    region: Option<String>,
+    emulated: Option<bool>,
+) -> FieldResult<leaderboard::SystemFilter> {
+    let platform_id = match platform {
+        Some(slug) => Some(
+            database
+                .platform_by_slug(&slug)
+                .ok_or_else(|| format!("platform: no platform {:?}", slug))?
+                .id,
+        ),
+        None => None,
+    };
+    let region_id = match region {
+        Some(slug) => Some(
+            database
+                .region_by_slug(&slug)
+                .ok_or_else(|| format!("region: no region {:?}", slug))?
+                .id,
+        ),
+        None => None,
+    };
+    Ok(leaderboard::SystemFilter {
+        platform_id,
+        region_id,
+        emulated,
+    })
+}
+
 impl SpeedrunsFields for Speedruns {
     fn field_game(
         &self,
@@ -75,10 +279,16 @@ impl SpeedrunsFields for Speedruns {
         _trail: &QueryTrail<'_, Game, Walked>,
         slug: String,
     ) -> Option<Game> {
-        match executor.context().database.game_by_slug(&slug) {
-            Some(game) => Some(Game(game)),
-            None => None,
-        }
+        executor.context().database.game_by_slug(&slug).map(Game)
+    }
+
+    fn field_user(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, User, Walked>,
+        slug: String,
+    ) -> Option<User> {
+        executor.context().database.user_by_slug(&slug).map(User)
     }
 
     fn field_node(
@@ -87,20 +297,155 @@ impl SpeedrunsFields for Speedruns {
         _trail: &QueryTrail<'_, Node, Walked>,
         id: ID,
     ) -> Option<Node> {
-        let database = &executor.context().database;
+        let context = executor.context();
+        let database = &context.database;
         match parse_global_id(&id) {
             Ok((id, node_type)) => match node_type {
                 NodeType::Game => database.game_by_id(id).map(|g| Node::Game(Game(g))),
-                NodeType::Run => database.run_by_id(id).map(|r| Node::Run(Run(r))),
+                NodeType::Run => context.run_by_id(id).map(|r| Node::Run(Run(r))),
                 NodeType::User => database.user_by_id(id).map(|u| Node::User(User(u))),
                 NodeType::Level => database.level_by_id(id).map(|l| Node::Level(Level(l))),
                 NodeType::Category => database
                     .category_by_id(id)
                     .map(|c| Node::Category(Category(c))),
+                NodeType::Platform => database
+                    .platform_by_id(id)
+                    .map(|p| Node::Platform(Platform(p))),
             },
             Err(_) => None,
         }
     }
+
+    fn field_search(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, SearchHit, Walked>,
+        query: String,
+        types: Option<Vec<SearchType>>,
+        first: i32,
+    ) -> Vec<SearchHit> {
+        let types: Vec<search::SearchType> = types
+            .unwrap_or_default()
+            .into_iter()
+            .map(|search_type| match search_type {
+                SearchType::Game => search::SearchType::Game,
+                SearchType::Category => search::SearchType::Category,
+                SearchType::Level => search::SearchType::Level,
+                SearchType::User => search::SearchType::User,
+            })
+            .collect();
+        let limit = usize::try_from(first).unwrap_or(0);
+
+        executor
+            .context()
+            .database
+            .search(&query, &types, limit)
+            .into_iter()
+            .map(SearchHit)
+            .collect()
+    }
+
+    fn field_games(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Game, Walked>,
+        genre: Option<String>,
+        developer: Option<String>,
+        publisher: Option<String>,
+        engine: Option<String>,
+        game_type: Option<String>,
+        release_year: Option<i32>,
+        romhack: Option<bool>,
+        first: i32,
+        offset: i32,
+    ) -> FieldResult<Vec<Game>> {
+        let context = executor.context();
+        let database = &context.database;
+        let mut query = database.query_games();
+        let tags = vec![
+            ("genre", "genre", db::GameTagKind::Genre, genre),
+            ("developer", "developer", db::GameTagKind::Developer, developer),
+            ("publisher", "publisher", db::GameTagKind::Publisher, publisher),
+            ("engine", "engine", db::GameTagKind::Engine, engine),
+            ("gameType", "game type", db::GameTagKind::GameType, game_type),
+        ];
+        for (argument, kind_name, kind, slug) in tags {
+            if let Some(slug) = slug {
+                let tag = database
+                    .game_tag_by_kind_and_slug(kind, &slug)
+                    .ok_or_else(|| format!("{}: no {} {:?}", argument, kind_name, slug))?;
+                query = query.tag(&tag);
+            }
+        }
+        if let Some(year) = release_year {
+            query = query.released_between(year, year);
+        }
+        if let Some(romhack) = romhack {
+            query = query.romhack(romhack);
+        }
+
+        Ok(query
+            .iter()
+            .skip(usize::try_from(offset).unwrap_or(0))
+            .take(usize::try_from(first).unwrap_or(0))
+            .map(Game)
+            .collect())
+    }
+
+    fn field_game_tags(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameTag, Walked>,
+        kind: GameTagKind,
+    ) -> Vec<GameTag> {
+        let kind = db_tag_kind(kind);
+        let mut tags = executor
+            .context()
+            .database
+            .game_tags()
+            .filter(|tag| tag.kind == kind)
+            .collect::<Vec<_>>();
+        tags.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+        tags.into_iter().map(GameTag).collect()
+    }
+}
+
+fn db_tag_kind(kind: GameTagKind) -> db::GameTagKind {
+    match kind {
+        GameTagKind::Genre => db::GameTagKind::Genre,
+        GameTagKind::Developer => db::GameTagKind::Developer,
+        GameTagKind::Publisher => db::GameTagKind::Publisher,
+        GameTagKind::Engine => db::GameTagKind::Engine,
+        GameTagKind::GameType => db::GameTagKind::GameType,
+    }
+}
+
+fn db_timing(timing: TimingMethod) -> db::TimingMethod {
+    match timing {
+        TimingMethod::Igt => db::TimingMethod::IGT,
+        TimingMethod::Rta => db::TimingMethod::RTA,
+        TimingMethod::RtaNl => db::TimingMethod::RTA_NL,
+    }
+}
+
+impl SearchHitFields for SearchHit {
+    fn field_score(&self, _executor: &Executor<'_, Context>) -> f64 {
+        f64::from(self.0.score)
+    }
+
+    fn field_node(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Node, Walked>,
+    ) -> Node {
+        match &self.0.result {
+            search::SearchResult::Game(game) => Node::Game(Game(game.clone())),
+            search::SearchResult::Category(category) =>
+                Node::Category(Category(category.clone())),
+            search::SearchResult::Level(level) => Node::Level(Level(level.clone())),
+            search::SearchResult::User(user) => Node::User(User(user.clone())),
+        }
+    }
 }
 
 impl GameFields for Game {
@@ -126,63 +471,560 @@ impl GameFields for Game {
 
     fn field_runs(
         &self,
-        _executor: &Executor<'_, Context>,
+        executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, Run, Walked>,
     ) -> Vec<Run> {
-        (self.0.runs().iter().map(|run| Run(run.clone())).collect())
+        match executor.context().game_with_runs(self.0.id) {
+            Some(game) => game.runs().iter().map(|run| Run(run.clone())).collect(),
+            None => vec![],
+        }
     }
 
     fn field_levels(
         &self,
-        executor: &Executor<'_, Context>,
+        _executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, Level, Walked>,
     ) -> Vec<Level> {
-        // TODO: not a full table scan
-        executor
-            .context()
-            .database
-            .levels()
-            .filter(|level| level.game_id == self.0.id)
-            .sorted_by(|a, b| a.name.cmp(&b.name))
-            .map(Level)
-            .collect()
+        self.0.levels().into_iter().map(Level).collect()
     }
 
     fn field_game_categories(
         &self,
-        executor: &Executor<'_, Context>,
+        _executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, Category, Walked>,
     ) -> Vec<Category> {
-        // TODO: not a full table scan
-        (executor
-            .context()
-            .database
+        (self
+            .0
             .categories()
-            .filter(|category| {
-                category.game_id == self.0.id && category.per == db::CategoryType::PerGame
-            })
-            .sorted_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)))
+            .into_iter()
+            .filter(|category| category.per == db::CategoryType::PerGame)
             .map(Category)
             .collect())
     }
 
     fn field_level_categories(
         &self,
-        executor: &Executor<'_, Context>,
+        _executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, Category, Walked>,
     ) -> Vec<Category> {
-        // TODO: not a full table scan
-        (executor
-            .context()
-            .database
+        (self
+            .0
             .categories()
-            .filter(|category| {
-                category.game_id == self.0.id && category.per == db::CategoryType::PerLevel
-            })
-            .sorted_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)))
+            .into_iter()
+            .filter(|category| category.per == db::CategoryType::PerLevel)
             .map(Category)
             .collect())
     }
+
+    fn field_variables(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Variable, Walked>,
+    ) -> Vec<Variable> {
+        self.0.variables().into_iter().map(Variable).collect()
+    }
+
+    fn field_platforms(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Platform, Walked>,
+    ) -> Vec<Platform> {
+        self.0.platforms().into_iter().map(Platform).collect()
+    }
+
+    fn field_regions(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Region, Walked>,
+    ) -> Vec<Region> {
+        self.0.regions().into_iter().map(Region).collect()
+    }
+
+    fn field_release_date(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.release_date.map(|date| date.to_string())
+    }
+
+    fn field_release_year(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0.release_date.map(|date| date.year())
+    }
+
+    fn field_is_romhack(&self, _executor: &Executor<'_, Context>) -> bool {
+        self.0.romhack
+    }
+
+    fn field_primary_timing(&self, _executor: &Executor<'_, Context>) -> TimingMethod {
+        match self.0.primary_timing {
+            db::TimingMethod::IGT => TimingMethod::Igt,
+            db::TimingMethod::RTA => TimingMethod::Rta,
+            db::TimingMethod::RTA_NL => TimingMethod::RtaNl,
+        }
+    }
+
+    fn field_genres(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameTag, Walked>,
+    ) -> Vec<GameTag> {
+        self.0
+            .tags(db::GameTagKind::Genre)
+            .into_iter()
+            .map(GameTag)
+            .collect()
+    }
+
+    fn field_developers(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameTag, Walked>,
+    ) -> Vec<GameTag> {
+        self.0
+            .tags(db::GameTagKind::Developer)
+            .into_iter()
+            .map(GameTag)
+            .collect()
+    }
+
+    fn field_publishers(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameTag, Walked>,
+    ) -> Vec<GameTag> {
+        self.0
+            .tags(db::GameTagKind::Publisher)
+            .into_iter()
+            .map(GameTag)
+            .collect()
+    }
+
+    fn field_engines(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameTag, Walked>,
+    ) -> Vec<GameTag> {
+        self.0
+            .tags(db::GameTagKind::Engine)
+            .into_iter()
+            .map(GameTag)
+            .collect()
+    }
+
+    fn field_game_types(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameTag, Walked>,
+    ) -> Vec<GameTag> {
+        self.0
+            .tags(db::GameTagKind::GameType)
+            .into_iter()
+            .map(GameTag)
+            .collect()
+    }
+
+    fn field_cover(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameAsset, Walked>,
+    ) -> Option<GameAsset> {
+        self.0.assets.cover.clone().map(GameAsset)
+    }
+
+    fn field_icon(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameAsset, Walked>,
+    ) -> Option<GameAsset> {
+        self.0.assets.icon.clone().map(GameAsset)
+    }
+
+    fn field_logo(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameAsset, Walked>,
+    ) -> Option<GameAsset> {
+        self.0.assets.logo.clone().map(GameAsset)
+    }
+
+    fn field_background(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameAsset, Walked>,
+    ) -> Option<GameAsset> {
+        self.0.assets.background.clone().map(GameAsset)
+    }
+
+    fn field_base_game(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Game, Walked>,
+    ) -> Option<Game> {
+        let context = executor.context();
+        let base_game = context.database.game_by_id(self.0.id)?.base_game()?;
+        Some(Game(base_game))
+    }
+
+    fn field_derived_games(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Game, Walked>,
+    ) -> Vec<Game> {
+        related_games(executor.context(), self.0.id, |game| game.derived_games())
+    }
+
+    fn field_romhacks(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Game, Walked>,
+    ) -> Vec<Game> {
+        related_games(executor.context(), self.0.id, |game| game.romhacks())
+    }
+
+    fn field_series(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Game, Walked>,
+    ) -> Vec<Game> {
+        related_games(executor.context(), self.0.id, |game| game.series())
+    }
+
+    fn field_moderators(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameModerator, Walked>,
+    ) -> Vec<GameModerator> {
+        match executor.context().database.game_by_id(self.0.id) {
+            Some(game) => game
+                .moderators()
+                .into_iter()
+                .map(|(user, kind)| GameModerator(user, kind))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn field_moderation(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, GameModeration, Walked>,
+    ) -> GameModeration {
+        // without its shard, the game has no runs to summarize
+        let game = executor
+            .context()
+            .game_with_runs(self.0.id)
+            .unwrap_or_else(|| self.0.clone());
+        GameModeration(moderation::moderation_stats(
+            &game.runs(),
+            &game.unverified_runs(),
+        ))
+    }
+
+    fn field_sum_of_best(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, SumOfBest, Walked>,
+        category_slug: String,
+        variables: Option<Vec<VariableFilter>>,
+        timing: Option<TimingMethod>,
+    ) -> FieldResult<Option<SumOfBest>> {
+        let game = match executor.context().game_with_runs(self.0.id) {
+            Some(game) => game,
+            None => return Ok(None),
+        };
+        let (category, levels, runs) = match level_runs(&game, &category_slug) {
+            Some(level_runs) => level_runs,
+            None => return Ok(None),
+        };
+        let values = level_variable_values(&category, &levels, variables)?;
+        let timing = timing.map_or_else(|| self.0.primary_timing().clone(), db_timing);
+        Ok(Some(SumOfBest(sum_of_best::sum_of_best(
+            &levels, &runs, &values, &timing,
+        ))))
+    }
+
+    fn field_sum_of_best_progression(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, SumOfBestProgress, Walked>,
+        category_slug: String,
+        variables: Option<Vec<VariableFilter>>,
+        timing: Option<TimingMethod>,
+    ) -> FieldResult<Vec<SumOfBestProgress>> {
+        let game = match executor.context().game_with_runs(self.0.id) {
+            Some(game) => game,
+            None => return Ok(vec![]),
+        };
+        let (category, levels, runs) = match level_runs(&game, &category_slug) {
+            Some(level_runs) => level_runs,
+            None => return Ok(vec![]),
+        };
+        let values = level_variable_values(&category, &levels, variables)?;
+        let timing = timing.map_or_else(|| self.0.primary_timing().clone(), db_timing);
+        Ok(
+            sum_of_best::sum_of_best_progression(&levels, &runs, &values, &timing)
+                .into_iter()
+                .map(SumOfBestProgress)
+                .collect(),
+        )
+    }
+}
+
+/// Finds a game's per-level category with the given slug, its levels, and the
+/// runs in it, or None if there's no such category. The game must be linked to
+/// the database with its runs.
+fn level_runs(
+    game: &DbLinked<db::Game>,
+    category_slug: &str,
+) -> Option<(
+    DbLinked<db::Category>,
+    Vec<DbLinked<db::Level>>,
+    Vec<DbLinked<db::Run>>,
+)> {
+    let category = game.per_level_category_by_slug(category_slug)?;
+    let runs = game
+        .database()
+        .query_runs()
+        .category(&category)
+        .without_banned_users()
+        .to_vec();
+    Some((category, game.levels(), runs))
+}
+
+/// Resolves variable and value slugs to value IDs by variable ID for each of
+/// the levels of a per-level category, by level ID, or an error naming the
+/// first that doesn't exist for any of them. Each level is only filtered by
+/// the variables that apply to it.
+fn level_variable_values(
+    category: &DbLinked<db::Category>,
+    levels: &[DbLinked<db::Level>],
+    filters: Option<Vec<VariableFilter>>,
+) -> FieldResult<BTreeMap<u64, BTreeMap<u64, u64>>> {
+    let filters = filters.unwrap_or_default();
+    for filter in &filters {
+        if levels.iter().all(|level| {
+            category
+                .variable_by_slug(Some(level), &filter.variable)
+                .is_none()
+        }) {
+            return Err(format!(
+                "variables: no variable {:?} for this category",
+                filter.variable
+            )
+            .into())
+        }
+    }
+
+    levels
+        .iter()
+        .map(|level| -> FieldResult<(u64, BTreeMap<u64, u64>)> {
+            let mut values = BTreeMap::new();
+            for filter in &filters {
+                if let Some(variable) =
+                    category.variable_by_slug(Some(level), &filter.variable)
+                {
+                    let value = variable.value_by_slug(&filter.value).ok_or_else(|| {
+                        format!(
+                            "variables: no value {:?} for variable {:?}",
+                            filter.value, filter.variable
+                        )
+                    })?;
+                    values.insert(variable.id, value.id);
+                }
+            }
+            Ok((*level.id(), values))
+        })
+        .collect()
+}
+
+/// Finds games related to the given one. Relations cross shards, so this uses
+/// the index.
+fn related_games(
+    context: &Context,
+    game_id: u64,
+    related: impl Fn(&DbLinked<db::Game>) -> Vec<DbLinked<db::Game>>,
+) -> Vec<Game> {
+    match context.database.game_by_id(game_id) {
+        Some(game) => related(&game).into_iter().map(Game).collect(),
+        None => vec![],
+    }
+}
+
+impl SumOfBestFields for SumOfBest {
+    fn field_sum_ms(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.sum_ms()).expect("impossibly long sum")
+    }
+
+    fn field_level_bests(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, LevelBest, Walked>,
+    ) -> Vec<LevelBest> {
+        self.0
+            .level_bests()
+            .iter()
+            .cloned()
+            .map(LevelBest)
+            .collect()
+    }
+
+    fn field_missing_levels(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Level, Walked>,
+    ) -> Vec<Level> {
+        self.0.missing_levels().iter().cloned().map(Level).collect()
+    }
+}
+
+impl LevelBestFields for LevelBest {
+    fn field_level(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Level, Walked>,
+    ) -> Level {
+        Level(self.0.level().clone())
+    }
+
+    fn field_time_ms(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.time_ms()).expect("impossibly long run")
+    }
+
+    fn field_run(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Run, Walked>,
+    ) -> Run {
+        Run(self.0.run().clone())
+    }
+}
+
+impl SumOfBestProgressFields for SumOfBestProgress {
+    fn field_level(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Level, Walked>,
+    ) -> Level {
+        Level(self.0.level().clone())
+    }
+
+    fn field_run(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Run, Walked>,
+    ) -> Run {
+        Run(self.0.run().clone())
+    }
+
+    fn field_improvement_ms(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.improvement_ms()).expect("impossibly long run")
+    }
+
+    fn field_sum_ms(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.sum_ms()).expect("impossibly long sum")
+    }
+
+    fn field_missing_levels(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.missing_levels()).expect("impossible number of levels")
+    }
+}
+
+impl GameModeratorFields for GameModerator {
+    fn field_user(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, User, Walked>,
+    ) -> User {
+        User(self.0.clone())
+    }
+
+    fn field_kind(&self, _executor: &Executor<'_, Context>) -> ModeratorKind {
+        match self.1 {
+            db::ModeratorKind::Moderator => ModeratorKind::Moderator,
+            db::ModeratorKind::SuperModerator => ModeratorKind::SuperModerator,
+        }
+    }
+}
+
+impl GameModerationFields for GameModeration {
+    fn field_queue_size(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.queue_size()).expect("impossibly long queue")
+    }
+
+    fn field_average_verification_ms(
+        &self,
+        _executor: &Executor<'_, Context>,
+    ) -> Option<f64> {
+        self.0.average_verification_ms().map(|ms| ms as f64)
+    }
+
+    fn field_rejections_by_moderator(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, ModeratorRejections, Walked>,
+    ) -> Vec<ModeratorRejections> {
+        let mut rejections: Vec<ModeratorRejections> = self
+            .0
+            .rejections_by_examiner_id()
+            .iter()
+            .map(|(user_id, rejections)| ModeratorRejections(*user_id, *rejections))
+            .collect();
+        rejections.sort_by_key(|rejections| std::cmp::Reverse(rejections.1));
+        rejections
+    }
+}
+
+impl ModeratorRejectionsFields for ModeratorRejections {
+    fn field_src_id(&self, _executor: &Executor<'_, Context>) -> String {
+        base36(self.0)
+    }
+
+    fn field_user(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, User, Walked>,
+    ) -> Option<User> {
+        executor.context().database.user_by_id(self.0).map(User)
+    }
+
+    fn field_rejections(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(self.1).expect("impossibly many rejections")
+    }
+}
+
+impl GameAssetFields for GameAsset {
+    fn field_uri(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.uri.clone()
+    }
+
+    fn field_width(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(self.0.width).expect("impossibly wide image")
+    }
+
+    fn field_height(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(self.0.height).expect("impossibly tall image")
+    }
+}
+
+impl GameTagFields for GameTag {
+    fn field_src_id(&self, _executor: &Executor<'_, Context>) -> String {
+        base36(self.0.id)
+    }
+
+    fn field_kind(&self, _executor: &Executor<'_, Context>) -> GameTagKind {
+        match self.0.kind {
+            db::GameTagKind::Genre => GameTagKind::Genre,
+            db::GameTagKind::Developer => GameTagKind::Developer,
+            db::GameTagKind::Publisher => GameTagKind::Publisher,
+            db::GameTagKind::Engine => GameTagKind::Engine,
+            db::GameTagKind::GameType => GameTagKind::GameType,
+        }
+    }
+
+    fn field_slug(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.slug.clone()
+    }
+
+    fn field_name(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.name.clone()
+    }
 }
 
 impl RunFields for Run {
@@ -199,6 +1041,14 @@ impl RunFields for Run {
             .expect("impossibly long wrong"))
     }
 
+    fn field_times(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, RunTimes, Walked>,
+    ) -> RunTimes {
+        RunTimes(self.0.times_ms.clone())
+    }
+
     fn field_category(
         &self,
         _executor: &Executor<'_, Context>,
@@ -222,26 +1072,118 @@ impl RunFields for Run {
             .map(|c| c.and_hms(12, 8, 4).timestamp() as f64)
     }
 
-    fn field_players(
-        &self,
-        executor: &Executor<'_, Context>,
-        _trail: &QueryTrail<'_, Player, Walked>,
-    ) -> Vec<Player> {
+    fn field_players(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Player, Walked>,
+    ) -> Vec<Player> {
+        self.0
+            .players()
+            .iter()
+            .map(|run_player| match run_player {
+                db::RunPlayer::UserId(user_id) => {
+                    let user = executor
+                        .context()
+                        .database
+                        .user_by_id(*user_id)
+                        .expect("database integrity");
+                    Player::User(User(user))
+                }
+                db::RunPlayer::GuestName(name) => Player::Guest(name.clone()),
+            })
+            .collect()
+    }
+
+    fn field_comment(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.comment.clone()
+    }
+
+    fn field_videos(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, RunVideo, Walked>,
+    ) -> Vec<RunVideo> {
+        self.0.videos.iter().cloned().map(RunVideo).collect()
+    }
+
+    fn field_platform(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Platform, Walked>,
+    ) -> Option<Platform> {
+        self.0.platform().map(Platform)
+    }
+
+    fn field_region(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Region, Walked>,
+    ) -> Option<Region> {
+        self.0.region().map(Region)
+    }
+
+    fn field_emulated(&self, _executor: &Executor<'_, Context>) -> bool {
+        self.0.emulated
+    }
+
+    fn field_examiner(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, User, Walked>,
+    ) -> Option<User> {
+        executor
+            .context()
+            .database
+            .user_by_id(self.0.examiner_id?)
+            .map(User)
+    }
+
+    fn field_verified(&self, _executor: &Executor<'_, Context>) -> Option<f64> {
+        self.0.verified.map(|verified| verified.timestamp() as f64)
+    }
+}
+
+impl RunTimesFields for RunTimes {
+    fn field_igt_ms(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0
+            .igt
+            .map(|ms| i32::try_from(ms).expect("impossibly long run"))
+    }
+
+    fn field_rta_ms(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0
+            .rta
+            .map(|ms| i32::try_from(ms).expect("impossibly long run"))
+    }
+
+    fn field_rta_nl_ms(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0
+            .rta_nl
+            .map(|ms| i32::try_from(ms).expect("impossibly long run"))
+    }
+}
+
+impl RunVideoFields for RunVideo {
+    fn field_url(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.url.clone()
+    }
+
+    fn field_platform(&self, _executor: &Executor<'_, Context>) -> VideoPlatform {
+        match self.0.platform {
+            db::VideoPlatform::YouTube => VideoPlatform::Youtube,
+            db::VideoPlatform::TwitchVod => VideoPlatform::TwitchVod,
+            db::VideoPlatform::Other => VideoPlatform::Other,
+        }
+    }
+
+    fn field_video_id(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.video_id.clone()
+    }
+
+    fn field_start_seconds(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
         self.0
-            .players()
-            .iter()
-            .map(|run_player| match run_player {
-                db::RunPlayer::UserId(user_id) => {
-                    let user = executor
-                        .context()
-                        .database
-                        .user_by_id(*user_id)
-                        .expect("database integrity");
-                    Player::User(User(user))
-                }
-                db::RunPlayer::GuestName(name) => Player::Guest(name.clone()),
-            })
-            .collect()
+            .start_s
+            .map(|start_s| i32::try_from(start_s).expect("impossibly long video"))
     }
 }
 
@@ -254,6 +1196,10 @@ impl LeaderboardRunFields for LeaderboardRun {
         Run(self.0.run().clone())
     }
 
+    fn field_time_ms(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.time_ms()).expect("impossibly long run")
+    }
+
     fn field_rank(&self, _executor: &Executor<'_, Context>) -> i32 {
         (i32::try_from(*self.0.rank()).expect("impossible number of runs"))
     }
@@ -265,6 +1211,34 @@ impl LeaderboardRunFields for LeaderboardRun {
     fn field_tied_rank(&self, _executor: &Executor<'_, Context>) -> i32 {
         (i32::try_from(*self.0.tied_rank()).expect("impossible number of runs"))
     }
+
+    fn field_previous_rank(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0
+            .previous_rank()
+            .map(|rank| i32::try_from(rank).expect("impossible number of runs"))
+    }
+
+    fn field_rank_change(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0
+            .rank_change()
+            .map(|change| i32::try_from(change).expect("impossible number of runs"))
+    }
+
+    fn field_is_obsolete(&self, _executor: &Executor<'_, Context>) -> bool {
+        *self.0.is_obsolete()
+    }
+
+    fn field_best_rank(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.best_rank()).expect("impossible number of runs")
+    }
+
+    fn field_superseded_by(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Run, Walked>,
+    ) -> Option<Run> {
+        self.0.superseded_by().clone().map(Run)
+    }
 }
 
 impl ProgressionRunFields for ProgressionRun {
@@ -285,7 +1259,17 @@ impl ProgressionRunFields for ProgressionRun {
         _executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, LeaderboardRun, Walked>,
     ) -> Option<LeaderboardRun> {
-        Some(LeaderboardRun(self.0.leaderboard_run().clone()))
+        self.0.leaderboard_run().clone().map(LeaderboardRun)
+    }
+
+    fn field_is_tie(&self, _executor: &Executor<'_, Context>) -> bool {
+        *self.0.is_tie()
+    }
+
+    fn field_days_standing(&self, _executor: &Executor<'_, Context>) -> Option<i32> {
+        self.0
+            .days_standing()
+            .map(|days| i32::try_from(days).expect("impossibly long-standing record"))
     }
 }
 
@@ -312,56 +1296,123 @@ impl CategoryFields for Category {
 
     fn field_leaderboard(
         &self,
-        _executor: &Executor<'_, Context>,
+        executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, LeaderboardRun, Walked>,
         level_slug: Option<String>,
-        _include_obsolete: bool,
-    ) -> Vec<LeaderboardRun> {
-        let level_id = level_slug.map(|level_slug| {
-            self.0
-                .game()
-                .level_by_slug(&level_slug)
-                .expect("level not found")
-                .id
-        });
-        let runs: Vec<DbLinked<db::Run>> = self
-            .0
-            .runs()
-            .iter()
-            .filter(|run| run.level_id == level_id && run.category_id == self.0.id)
-            .cloned()
-            .collect();
-
-        let ranked = leaderboard::leaderboard(&runs);
+        include_obsolete: bool,
+        variables: Option<Vec<VariableFilter>>,
+        platform: Option<String>,
+        region: Option<String>,
+        emulated: Option<bool>,
+        timing: Option<TimingMethod>,
+        as_of: Option<String>,
+        compare_to: Option<String>,
+    ) -> FieldResult<Vec<LeaderboardRun>> {
+        let level = match level_slug {
+            Some(level_slug) => Some(
+                self.0
+                    .game()
+                    .level_by_slug(&level_slug)
+                    .ok_or_else(|| format!("levelSlug: no level {:?}", level_slug))?,
+            ),
+            None => None,
+        };
+        let values = variable_values(&self.0, level.as_deref(), variables)?;
+        let system = system_filter(self.0.database(), platform, region, emulated)?;
+        let as_of = parse_date("asOf", as_of)?;
+        let compare_to = parse_date("compareTo", compare_to)?;
+        let runs = match executor.context().game_database(self.0.game_id) {
+            Some(database) => database
+                .query_runs()
+                .category(&self.0)
+                .level(level.as_deref())
+                .without_banned_users()
+                .to_vec(),
+            None => vec![],
+        };
+
+        let timing =
+            timing.map_or_else(|| self.0.game().primary_timing().clone(), db_timing);
+        let mut ranked = leaderboard::leaderboard(
+            &runs,
+            &values,
+            &system,
+            &timing,
+            include_obsolete,
+            as_of,
+        );
+        if let Some(compare_to) = compare_to {
+            let previous = leaderboard::leaderboard(
+                &runs,
+                &values,
+                &system,
+                &timing,
+                false,
+                Some(compare_to),
+            );
+            leaderboard::compare_ranks(&mut ranked, &previous);
+        }
 
-        (ranked.iter().map(|r| LeaderboardRun(r.clone())).collect())
+        Ok(ranked.iter().map(|r| LeaderboardRun(r.clone())).collect())
     }
 
     fn field_progression(
         &self,
-        _executor: &Executor<'_, Context>,
+        executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, ProgressionRun, Walked>,
         level_slug: Option<String>,
-        _include_ties: bool,
-    ) -> Vec<ProgressionRun> {
-        let level_id = level_slug.map(|level_slug| {
-            self.0
-                .game()
-                .level_by_slug(&level_slug)
-                .expect("level not found")
-                .id
-        });
-        let runs: Vec<DbLinked<db::Run>> = self
-            .0
-            .runs()
-            .iter()
-            .filter(|run| run.level_id == level_id && run.category_id == self.0.id)
-            .cloned()
-            .collect();
-
-        let progress = progression::progression(&runs);
+        include_ties: bool,
+        variables: Option<Vec<VariableFilter>>,
+        timing: Option<TimingMethod>,
+    ) -> FieldResult<Vec<ProgressionRun>> {
+        let level = match level_slug {
+            Some(level_slug) => Some(
+                self.0
+                    .game()
+                    .level_by_slug(&level_slug)
+                    .ok_or_else(|| format!("levelSlug: no level {:?}", level_slug))?,
+            ),
+            None => None,
+        };
+        let values = variable_values(&self.0, level.as_deref(), variables)?;
+        let runs = match executor.context().game_database(self.0.game_id) {
+            Some(database) => database
+                .query_runs()
+                .category(&self.0)
+                .level(level.as_deref())
+                .without_banned_users()
+                .to_vec(),
+            None => vec![],
+        };
+
+        let timing =
+            timing.map_or_else(|| self.0.game().primary_timing().clone(), db_timing);
+        let progress = progression::progression(&runs, &values, &timing, include_ties);
+
+        Ok(progress.iter().map(|r| ProgressionRun(r.clone())).collect())
+    }
 
-        (progress.iter().map(|r| ProgressionRun(r.clone())).collect())
+    fn field_variables(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Variable, Walked>,
+        level_slug: Option<String>,
+    ) -> FieldResult<Vec<Variable>> {
+        let level = match level_slug {
+            Some(level_slug) => Some(
+                self.0
+                    .game()
+                    .level_by_slug(&level_slug)
+                    .ok_or_else(|| format!("levelSlug: no level {:?}", level_slug))?,
+            ),
+            None => None,
+        };
+        Ok(self
+            .0
+            .variables_for(level.as_deref())
+            .into_iter()
+            .map(Variable)
+            .collect())
     }
 }
 
@@ -381,6 +1432,217 @@ impl UserFields for User {
     fn field_src_slug(&self, _executor: &Executor<'_, Context>) -> String {
         src_slugify(&self.0.name)
     }
+
+    fn field_name(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.name.clone()
+    }
+
+    fn field_location(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, UserLocation, Walked>,
+    ) -> Option<UserLocation> {
+        self.0.location.clone().map(UserLocation)
+    }
+
+    fn field_links(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, UserLinks, Walked>,
+    ) -> UserLinks {
+        UserLinks(self.0.links.clone())
+    }
+
+    fn field_role(&self, _executor: &Executor<'_, Context>) -> UserRole {
+        match self.0.role {
+            db::UserRole::User => UserRole::User,
+            db::UserRole::Trusted => UserRole::Trusted,
+            db::UserRole::Moderator => UserRole::Moderator,
+            db::UserRole::ContentModerator => UserRole::ContentModerator,
+            db::UserRole::Admin => UserRole::Admin,
+            db::UserRole::Programmer => UserRole::Programmer,
+            db::UserRole::Banned => UserRole::Banned,
+        }
+    }
+
+    fn field_is_banned(&self, _executor: &Executor<'_, Context>) -> bool {
+        self.0.is_banned()
+    }
+
+    fn field_name_style(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, UserNameStyle, Walked>,
+    ) -> Option<UserNameStyle> {
+        self.0.name_style.clone().map(UserNameStyle)
+    }
+
+    fn field_moderated_games(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Game, Walked>,
+    ) -> Vec<Game> {
+        let context = executor.context();
+        match context.database.user_by_id(self.0.id) {
+            Some(user) => user.moderated_games().into_iter().map(Game).collect(),
+            None => vec![],
+        }
+    }
+
+    fn field_sum_of_best(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, SumOfBest, Walked>,
+        game_slug: String,
+        category_slug: String,
+        variables: Option<Vec<VariableFilter>>,
+        timing: Option<TimingMethod>,
+    ) -> FieldResult<Option<SumOfBest>> {
+        let context = executor.context();
+        let game = match context
+            .database
+            .game_by_slug(&game_slug)
+            .and_then(|game| context.game_with_runs(game.id))
+        {
+            Some(game) => game,
+            None => return Ok(None),
+        };
+        let (category, levels, runs) = match level_runs(&game, &category_slug) {
+            Some(level_runs) => level_runs,
+            None => return Ok(None),
+        };
+        let values = level_variable_values(&category, &levels, variables)?;
+        let timing = timing.map_or_else(|| game.primary_timing().clone(), db_timing);
+        Ok(Some(SumOfBest(sum_of_best::personal_sum_of_best(
+            &levels,
+            &runs,
+            &values,
+            &timing,
+            &db::RunPlayer::UserId(self.0.id),
+        ))))
+    }
+
+    fn field_personal_bests(
+        &self,
+        executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, PersonalBest, Walked>,
+    ) -> Vec<PersonalBest> {
+        executor
+            .context()
+            .user_databases(self.0.id)
+            .iter()
+            .flat_map(|database| personal_bests::personal_bests(database, self.0.id))
+            .map(PersonalBest)
+            .collect()
+    }
+}
+
+impl PersonalBestFields for PersonalBest {
+    fn field_run(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, Run, Walked>,
+    ) -> Run {
+        Run(self.0.leaderboard_run().run().clone())
+    }
+
+    fn field_rank(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.leaderboard_run().rank()).expect("impossible number of runs")
+    }
+
+    fn field_tied_rank(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.leaderboard_run().tied_rank())
+            .expect("impossible number of runs")
+    }
+
+    fn field_is_tied(&self, _executor: &Executor<'_, Context>) -> bool {
+        *self.0.leaderboard_run().is_tied()
+    }
+
+    fn field_board_size(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(*self.0.board_size()).expect("impossible number of runs")
+    }
+}
+
+impl UserLocationFields for UserLocation {
+    fn field_country_code(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.country_code.clone()
+    }
+
+    fn field_country(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.country.clone()
+    }
+
+    fn field_region_code(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.region_code.clone()
+    }
+
+    fn field_region(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.region.clone()
+    }
+}
+
+impl UserLinksFields for UserLinks {
+    fn field_twitch(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.twitch.clone()
+    }
+
+    fn field_youtube(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.youtube.clone()
+    }
+
+    fn field_twitter(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.twitter.clone()
+    }
+
+    fn field_hitbox(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.hitbox.clone()
+    }
+
+    fn field_speedrunslive(&self, _executor: &Executor<'_, Context>) -> Option<String> {
+        self.0.speedrunslive.clone()
+    }
+}
+
+impl UserNameStyleFields for UserNameStyle {
+    fn field_is_gradient(&self, _executor: &Executor<'_, Context>) -> bool {
+        match self.0 {
+            db::UserNameStyle::Solid(_) => false,
+            db::UserNameStyle::Gradient { .. } => true,
+        }
+    }
+
+    fn field_color_from(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, UserColor, Walked>,
+    ) -> UserColor {
+        match &self.0 {
+            db::UserNameStyle::Solid(color) => UserColor(color.clone()),
+            db::UserNameStyle::Gradient { from, .. } => UserColor(from.clone()),
+        }
+    }
+
+    fn field_color_to(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, UserColor, Walked>,
+    ) -> UserColor {
+        match &self.0 {
+            db::UserNameStyle::Solid(color) => UserColor(color.clone()),
+            db::UserNameStyle::Gradient { to, .. } => UserColor(to.clone()),
+        }
+    }
+}
+
+impl UserColorFields for UserColor {
+    fn field_dark(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.dark.clone()
+    }
+
+    fn field_light(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.light.clone()
+    }
 }
 
 impl PlayerFields for Player {
@@ -433,78 +1695,208 @@ impl LevelFields for Level {
 
     fn field_categories(
         &self,
-        executor: &Executor<'_, Context>,
+        _executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, Category, Walked>,
     ) -> Vec<Category> {
-        // TODO: not a full table scan
-        (executor
-            .context()
-            .database
+        (self
+            .0
+            .game()
             .categories()
-            .filter(|category| {
-                category.game_id == self.0.id && category.per == db::CategoryType::PerLevel
-            })
+            .into_iter()
+            .filter(|category| category.per == db::CategoryType::PerLevel)
             .map(Category)
             .collect())
     }
 
     fn field_leaderboard(
         &self,
-        _executor: &Executor<'_, Context>,
+        executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, LeaderboardRun, Walked>,
         category_slug: Option<String>,
-        _include_obsolete: bool,
-    ) -> Vec<LeaderboardRun> {
-        let category_id = category_slug.map(|category_slug| {
-            self.0
+        include_obsolete: bool,
+        variables: Option<Vec<VariableFilter>>,
+        platform: Option<String>,
+        region: Option<String>,
+        emulated: Option<bool>,
+        timing: Option<TimingMethod>,
+        as_of: Option<String>,
+        compare_to: Option<String>,
+    ) -> FieldResult<Vec<LeaderboardRun>> {
+        let category = match category_slug {
+            Some(category_slug) => self
+                .0
                 .game()
                 .per_level_category_by_slug(&category_slug)
-                .expect("category not found")
-                .id
-        });
-        let runs: Vec<DbLinked<db::Run>> = self
-            .0
-            .game()
-            .runs()
-            .iter()
-            .filter(|run| {
-                Some(run.category_id) == category_id && run.level_id == Some(self.0.id)
-            })
-            .cloned()
-            .collect();
-
-        let ranked = leaderboard::leaderboard(&runs);
+                .ok_or_else(|| {
+                    format!("categorySlug: no per-level category {:?}", category_slug)
+                })?,
+            None => return Ok(vec![]),
+        };
+        let values = variable_values(&category, Some(&self.0), variables)?;
+        let system = system_filter(self.0.database(), platform, region, emulated)?;
+        let as_of = parse_date("asOf", as_of)?;
+        let compare_to = parse_date("compareTo", compare_to)?;
+        let runs = match executor.context().game_database(self.0.game_id) {
+            Some(database) => database
+                .query_runs()
+                .category(&category)
+                .level(Some(&self.0))
+                .without_banned_users()
+                .to_vec(),
+            None => vec![],
+        };
+
+        let timing =
+            timing.map_or_else(|| self.0.game().primary_timing().clone(), db_timing);
+        let mut ranked = leaderboard::leaderboard(
+            &runs,
+            &values,
+            &system,
+            &timing,
+            include_obsolete,
+            as_of,
+        );
+        if let Some(compare_to) = compare_to {
+            let previous = leaderboard::leaderboard(
+                &runs,
+                &values,
+                &system,
+                &timing,
+                false,
+                Some(compare_to),
+            );
+            leaderboard::compare_ranks(&mut ranked, &previous);
+        }
 
-        (ranked.iter().map(|r| LeaderboardRun(r.clone())).collect())
+        Ok(ranked.iter().map(|r| LeaderboardRun(r.clone())).collect())
     }
 
     fn field_progression(
         &self,
-        _executor: &Executor<'_, Context>,
+        executor: &Executor<'_, Context>,
         _trail: &QueryTrail<'_, ProgressionRun, Walked>,
         category_slug: Option<String>,
-        _include_ties: bool,
-    ) -> Vec<ProgressionRun> {
-        let category_id = category_slug.map(|category_slug| {
-            self.0
+        include_ties: bool,
+        variables: Option<Vec<VariableFilter>>,
+        timing: Option<TimingMethod>,
+    ) -> FieldResult<Vec<ProgressionRun>> {
+        let category = match category_slug {
+            Some(category_slug) => self
+                .0
                 .game()
                 .per_level_category_by_slug(&category_slug)
-                .expect("category not found")
-                .id
-        });
-        let runs: Vec<DbLinked<db::Run>> = self
-            .0
-            .game()
-            .runs()
-            .iter()
-            .filter(|run| {
-                Some(run.category_id) == category_id && run.level_id == Some(self.0.id)
-            })
+                .ok_or_else(|| {
+                    format!("categorySlug: no per-level category {:?}", category_slug)
+                })?,
+            None => return Ok(vec![]),
+        };
+        let values = variable_values(&category, Some(&self.0), variables)?;
+        let runs = match executor.context().game_database(self.0.game_id) {
+            Some(database) => database
+                .query_runs()
+                .category(&category)
+                .level(Some(&self.0))
+                .without_banned_users()
+                .to_vec(),
+            None => vec![],
+        };
+
+        let timing =
+            timing.map_or_else(|| self.0.game().primary_timing().clone(), db_timing);
+        let progress = progression::progression(&runs, &values, &timing, include_ties);
+
+        Ok(progress.iter().map(|r| ProgressionRun(r.clone())).collect())
+    }
+}
+
+impl VariableFields for Variable {
+    fn field_src_id(&self, _executor: &Executor<'_, Context>) -> String {
+        base36(self.0.id)
+    }
+
+    fn field_slug(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.slug.clone()
+    }
+
+    fn field_name(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.name.clone()
+    }
+
+    fn field_is_subcategory(&self, _executor: &Executor<'_, Context>) -> bool {
+        self.0.is_subcategory
+    }
+
+    fn field_default_value(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, VariableValue, Walked>,
+    ) -> Option<VariableValue> {
+        self.0
+            .default_value_id
+            .and_then(|value_id| self.0.value_by_id(value_id))
             .cloned()
-            .collect();
+            .map(VariableValue)
+    }
+
+    fn field_values(
+        &self,
+        _executor: &Executor<'_, Context>,
+        _trail: &QueryTrail<'_, VariableValue, Walked>,
+    ) -> Vec<VariableValue> {
+        self.0.values.iter().cloned().map(VariableValue).collect()
+    }
+}
+
+impl VariableValueFields for VariableValue {
+    fn field_src_id(&self, _executor: &Executor<'_, Context>) -> String {
+        base36(self.0.id)
+    }
+
+    fn field_slug(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.slug.clone()
+    }
+
+    fn field_label(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.label.clone()
+    }
+
+    fn field_rules(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.rules.clone()
+    }
+}
+
+impl PlatformFields for Platform {
+    fn field_id(&self, _executor: &Executor<'_, Context>) -> ID {
+        global_id(self.0.id, NodeType::Platform)
+    }
+
+    fn field_src_id(&self, _executor: &Executor<'_, Context>) -> String {
+        base36(self.0.id)
+    }
+
+    fn field_slug(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.slug.clone()
+    }
+
+    fn field_name(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.name.clone()
+    }
+
+    fn field_released(&self, _executor: &Executor<'_, Context>) -> i32 {
+        i32::try_from(self.0.released).expect("impossibly late release")
+    }
+}
 
-        let progress = progression::progression(&runs);
+impl RegionFields for Region {
+    fn field_src_id(&self, _executor: &Executor<'_, Context>) -> String {
+        base36(self.0.id)
+    }
+
+    fn field_slug(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.slug.clone()
+    }
 
-        (progress.iter().map(|r| ProgressionRun(r.clone())).collect())
+    fn field_name(&self, _executor: &Executor<'_, Context>) -> String {
+        self.0.name.clone()
     }
 }
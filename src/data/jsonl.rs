@@ -0,0 +1,312 @@
+//! Loads [Tables] from a directory of normalized JSONL files, as written by the
+//! `normalize` binary.
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::File,
+    io::{prelude::*, BufReader},
+    path::{Path, PathBuf},
+};
+
+use err_derive::Error;
+use flate2::read::GzDecoder;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value as JsonValue;
+
+use crate::data::{
+    database::Tables,
+    migrations::{Manifest, MigrationError},
+};
+
+/// What to do with records that can't be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Fail on the first bad record.
+    Strict,
+    /// Log and skip bad records, counting them in the [LoadReport].
+    Skip,
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error(
+        display = "required table {:?} not found in {:?} as .jsonl or .jsonl.gz",
+        table,
+        dir
+    )]
+    MissingTable { table: &'static str, dir: PathBuf },
+    #[error(display = "failed to read {:?}: {}", path, error)]
+    Io {
+        path:  PathBuf,
+        error: std::io::Error,
+    },
+    #[error(display = "{}", _0)]
+    Migration(MigrationError),
+    #[error(display = "{}", _0)]
+    BadRecord(RecordError),
+}
+
+impl From<MigrationError> for LoadError {
+    fn from(error: MigrationError) -> Self {
+        LoadError::Migration(error)
+    }
+}
+
+/// A record that couldn't be parsed, migrated, or deserialized.
+#[derive(Debug, Error)]
+pub struct RecordError {
+    pub path:  PathBuf,
+    /// The 1-based line number of the record.
+    pub line:  usize,
+    pub error: RecordErrorKind,
+}
+
+#[derive(Debug, Error)]
+pub enum RecordErrorKind {
+    #[error(display = "{}", _0)]
+    Json(serde_json::Error),
+    #[error(display = "{}", _0)]
+    Migration(MigrationError),
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.error)
+    }
+}
+
+/// The number of bad records skipped from each table by [Tables::load_dir].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    pub skipped: BTreeMap<&'static str, usize>,
+}
+
+impl LoadReport {
+    pub fn total_skipped(&self) -> usize {
+        self.skipped.values().sum()
+    }
+}
+
+impl Tables {
+    /// Loads every table from a directory of normalized `{table}.jsonl` or
+    /// `{table}.jsonl.gz` files, migrating records from older format versions.
+    ///
+    /// Every table is required, even if it's empty, unless it was added in a
+    /// later format version than the directory's.
+    pub fn load_dir(
+        dir: impl AsRef<Path>,
+        mode: LoadMode,
+    ) -> Result<(Tables, LoadReport), LoadError> {
+        let dir = dir.as_ref();
+        let manifest = Manifest::read(dir)?;
+        let mut report = LoadReport::default();
+
+        let runs = load_table(dir, "runs", &manifest, mode, &mut report)?;
+        let users = load_table(dir, "users", &manifest, mode, &mut report)?;
+        let games = load_table(dir, "games", &manifest, mode, &mut report)?;
+        let categories = load_table(dir, "categories", &manifest, mode, &mut report)?;
+        let levels = load_table(dir, "levels", &manifest, mode, &mut report)?;
+        let variables = load_table(dir, "variables", &manifest, mode, &mut report)?;
+        let platforms = load_table(dir, "platforms", &manifest, mode, &mut report)?;
+        let regions = load_table(dir, "regions", &manifest, mode, &mut report)?;
+        let game_tags = load_table(dir, "game_tags", &manifest, mode, &mut report)?;
+        let game_relations =
+            load_table(dir, "game_relations", &manifest, mode, &mut report)?;
+        let unverified_runs =
+            load_table(dir, "unverified_runs", &manifest, mode, &mut report)?;
+
+        Ok((
+            Tables::new(
+                runs,
+                users,
+                games,
+                categories,
+                levels,
+                variables,
+                platforms,
+                regions,
+                game_tags,
+                game_relations,
+                unverified_runs,
+            ),
+            report,
+        ))
+    }
+}
+
+/// Tables that were added after the first format version, with the version
+/// that added them. They're treated as empty in directories of older versions.
+const ADDED_TABLES: &[(&str, u32)] = &[
+    ("variables", 2),
+    ("platforms", 4),
+    ("regions", 4),
+    ("game_tags", 6),
+    ("game_relations", 7),
+    ("unverified_runs", 8),
+];
+
+fn load_table<T: DeserializeOwned>(
+    dir: &Path,
+    table: &'static str,
+    manifest: &Manifest,
+    mode: LoadMode,
+    report: &mut LoadReport,
+) -> Result<Vec<T>, LoadError> {
+    let plain = dir.join(format!("{}.jsonl", table));
+    let gzipped = dir.join(format!("{}.jsonl.gz", table));
+    let (path, reader): (PathBuf, Box<dyn BufRead>) = if plain.exists() {
+        let file = File::open(&plain).map_err(|error| LoadError::Io {
+            path: plain.clone(),
+            error,
+        })?;
+        (plain, Box::new(BufReader::new(file)))
+    } else if gzipped.exists() {
+        let file = File::open(&gzipped).map_err(|error| LoadError::Io {
+            path: gzipped.clone(),
+            error,
+        })?;
+        (
+            gzipped,
+            Box::new(BufReader::new(GzDecoder::new(BufReader::new(file)))),
+        )
+    } else if ADDED_TABLES
+        .iter()
+        .any(|(added, version)| *added == table && manifest.version < *version)
+    {
+        debug!(
+            "{:?} predates the {:?} table, leaving it empty.",
+            dir, table
+        );
+        return Ok(vec![])
+    } else {
+        return Err(LoadError::MissingTable {
+            table,
+            dir: dir.to_path_buf(),
+        })
+    };
+
+    debug!("Loading {:?}...", path);
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| LoadError::Io {
+            path: path.clone(),
+            error,
+        })?;
+        if line.trim().is_empty() {
+            continue
+        }
+
+        let record = serde_json::from_str::<JsonValue>(&line)
+            .map_err(RecordErrorKind::Json)
+            .and_then(|mut record| {
+                manifest
+                    .migrate(table, &mut record)
+                    .map_err(RecordErrorKind::Migration)?;
+                T::deserialize(record).map_err(RecordErrorKind::Json)
+            });
+
+        match record {
+            Ok(record) => records.push(record),
+            Err(error) => {
+                let error = RecordError {
+                    path: path.clone(),
+                    line: index + 1,
+                    error,
+                };
+                match mode {
+                    LoadMode::Strict => return Err(LoadError::BadRecord(error)),
+                    LoadMode::Skip => {
+                        warn!("Skipping bad record: {}", error);
+                        *report.skipped.entry(table).or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn write_tables(dir: &Path, bad_user: bool) {
+        let game = r#"{"id":1,"created":null,"slug":"game","src_slug":"game","name":"Game","primary_timing":"RTA"}"#;
+        std::fs::write(dir.join("games.jsonl"), format!("{}\n", game)).expect("written");
+        std::fs::write(dir.join("categories.jsonl"), "").expect("written");
+        std::fs::write(dir.join("levels.jsonl"), "").expect("written");
+        std::fs::write(dir.join("runs.jsonl"), "\n").expect("written");
+
+        let mut users = GzEncoder::new(
+            File::create(dir.join("users.jsonl.gz")).expect("created"),
+            Compression::default(),
+        );
+        writeln!(users, r#"{{"created":null,"slug":"a","name":"A","id":1}}"#)
+            .expect("written");
+        if bad_user {
+            writeln!(users, r#"{{"created":null,"slug":"b","name":"B"}}"#)
+                .expect("written");
+        }
+        users.finish().expect("written");
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = tempfile::tempdir().expect("temp dir");
+
+        write_tables(dir.path(), false);
+        let (tables, report) =
+            Tables::load_dir(dir.path(), LoadMode::Strict).expect("tables loaded");
+        assert_eq!(tables.games().len(), 1);
+        assert_eq!(tables.users().len(), 1);
+        assert_eq!(report.total_skipped(), 0);
+
+        write_tables(dir.path(), true);
+        match Tables::load_dir(dir.path(), LoadMode::Strict) {
+            Err(LoadError::BadRecord(error)) => {
+                assert!(error.path.ends_with("users.jsonl.gz"));
+                assert_eq!(error.line, 2);
+            }
+            other => panic!("expected bad record, got {:?}", other.map(|_| ())),
+        }
+        let (tables, report) =
+            Tables::load_dir(dir.path(), LoadMode::Skip).expect("tables loaded");
+        assert_eq!(tables.users().len(), 1);
+        assert_eq!(report.skipped.get("users"), Some(&1));
+
+        std::fs::remove_file(dir.path().join("levels.jsonl")).expect("removed");
+        match Tables::load_dir(dir.path(), LoadMode::Skip) {
+            Err(LoadError::MissingTable { table, .. }) => assert_eq!(table, "levels"),
+            other => panic!("expected missing table, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_load_dir_non_object_record() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        write_tables(dir.path(), false);
+        std::fs::write(dir.path().join("runs.jsonl"), "[1]\n").expect("written");
+
+        match Tables::load_dir(dir.path(), LoadMode::Strict) {
+            Err(LoadError::BadRecord(RecordError {
+                path,
+                line,
+                error: RecordErrorKind::Migration(MigrationError::NotAnObject { table }),
+            })) => {
+                assert!(path.ends_with("runs.jsonl"));
+                assert_eq!(line, 1);
+                assert_eq!(table, "runs");
+            }
+            other => panic!("expected bad record, got {:?}", other.map(|_| ())),
+        }
+
+        let (tables, report) =
+            Tables::load_dir(dir.path(), LoadMode::Skip).expect("tables loaded");
+        assert_eq!(tables.runs().len(), 0);
+        assert_eq!(report.skipped.get("runs"), Some(&1));
+    }
+}
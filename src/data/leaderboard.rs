@@ -1,5 +1,9 @@
-use std::{collections::HashSet, convert::TryFrom};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
 
+use chrono::{DateTime, NaiveDate, Utc};
 use getset::Getters;
 use serde::Serialize;
 
@@ -8,51 +12,126 @@ use crate::data::{database::Linked, types::*};
 #[derive(Debug, Clone, Getters, Serialize)]
 #[get = "pub"]
 pub struct LeaderboardRun {
-    rank:      u64,
-    time_ms:   u64,
-    is_tied:   bool,
-    tied_rank: u64,
-    run:       Linked<Run>,
+    /// For an obsolete run, the rank it would have among the other runners'
+    /// best runs.
+    rank:          u64,
+    time_ms:       u64,
+    is_tied:       bool,
+    tied_rank:     u64,
+    /// Whether the same runner has a faster run on this leaderboard.
+    is_obsolete:   bool,
+    /// The tied rank of the runner's best run on this leaderboard.
+    best_rank:     u64,
+    /// For an obsolete run, the earliest of the runner's faster runs that was
+    /// performed on or after it. None if each of them was performed before it,
+    /// or if when either run was performed isn't known.
+    superseded_by: Option<Linked<Run>>,
+    /// The tied rank of the runner's best run on a leaderboard this one has
+    /// been compared to with [compare_ranks], if they were on it.
+    previous_rank: Option<u64>,
+    run:           Linked<Run>,
 }
 
-/// Ranks a set of runs (all for the same game/category/level) using the
-/// timing specified for the game rules, then by run date, then by
-/// submission datetime, discarding lower-ranked runs by the same runner.
-pub fn leaderboard(runs: &[Linked<Run>]) -> Vec<LeaderboardRun> {
-    let mut runs: Vec<Linked<Run>> = runs.to_vec();
-
-    if runs.is_empty() {
-        return vec![]
+impl LeaderboardRun {
+    /// How many places the runner has moved up (or down, if negative) since
+    /// the leaderboard this one was compared to, if they were on it.
+    pub fn rank_change(&self) -> Option<i64> {
+        self.previous_rank.map(|previous_rank| {
+            i64::try_from(previous_rank).unwrap() - i64::try_from(self.best_rank).unwrap()
+        })
     }
+}
 
-    let game = runs[0].game();
+/// Restricts a leaderboard to runs on a given platform or region, or on
+/// emulators or real hardware. Criteria that are None match every run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemFilter {
+    pub platform_id: Option<u64>,
+    pub region_id:   Option<u64>,
+    pub emulated:    Option<bool>,
+}
 
-    runs.sort_by_key(|run| {
-        let time_ms = run
-            .times_ms()
-            .get(game.primary_timing())
-            .expect("run missing primary timing");
+impl SystemFilter {
+    /// Returns whether the given run matches every criterion of this filter.
+    pub fn matches(&self, run: &Run) -> bool {
+        self.platform_id
+            .map_or(true, |platform_id| run.platform_id == Some(platform_id))
+            && self
+                .region_id
+                .map_or(true, |region_id| run.region_id == Some(region_id))
+            && self
+                .emulated
+                .map_or(true, |emulated| run.emulated == emulated)
+    }
+}
+
+/// Ranks a set of runs (all for the same game/category/level) by their time
+/// using the given `timing` method, then by run date, then by submission
+/// datetime. Lower-ranked runs by the same runner are obsolete, and are
+/// discarded unless `include_obsolete` is set.
+///
+/// Only runs with every one of the given variable `values` (value IDs by
+/// variable ID) are ranked, so that subcategories get their own leaderboards,
+/// and only those matching the `system` filter. Runs without a time for the
+/// `timing` method are skipped, and if there's an `as_of` date, so are runs
+/// performed after it. Runs without a date are treated as performed when they
+/// were submitted, and skipped if that isn't known either.
+pub fn leaderboard(
+    runs: &[Linked<Run>],
+    values: &BTreeMap<u64, u64>,
+    system: &SystemFilter,
+    timing: &TimingMethod,
+    include_obsolete: bool,
+    as_of: Option<NaiveDate>,
+) -> Vec<LeaderboardRun> {
+    let mut runs: Vec<Linked<Run>> = runs
+        .iter()
+        .filter(|run| {
+            run.times_ms().get(timing).is_some()
+                && run.has_values(values)
+                && system.matches(run)
+                && as_of.map_or(true, |as_of| {
+                    performed_on(run).map_or(false, |date| date <= as_of)
+                })
+        })
+        .cloned()
+        .collect();
 
-        (time_ms, *run.date(), *run.created())
-    });
+    runs.sort_by_key(|run| (run.times_ms().get(timing), *run.date(), *run.created()));
 
-    let mut ranked_players: HashSet<&Vec<RunPlayer>> = HashSet::new();
+    // each runner's runs so far, which are at least as fast as their next run
+    let mut runner_runs: HashMap<&Vec<RunPlayer>, Vec<&Linked<Run>>> = HashMap::new();
+    let mut best_ranks: HashMap<&Vec<RunPlayer>, u64> = HashMap::new();
 
     let mut leaderboard: Vec<LeaderboardRun> = vec![];
+    let mut obsolete: Vec<LeaderboardRun> = vec![];
 
     let mut n = 0;
     for run in runs.iter() {
-        if !ranked_players.insert(run.players()) {
-            // this run is obsolete, skip it
+        let time_ms = run.times_ms().get(timing).expect("run missing timing");
+
+        let previous_runs = runner_runs.entry(run.players()).or_default();
+        if !previous_runs.is_empty() {
+            if include_obsolete {
+                obsolete.push(LeaderboardRun {
+                    rank: 0,
+                    time_ms,
+                    is_tied: false,
+                    tied_rank: 0,
+                    is_obsolete: true,
+                    best_rank: best_ranks[run.players()],
+                    superseded_by: superseded_by(run, time_ms, previous_runs, timing),
+                    previous_rank: None,
+                    run: run.clone(),
+                });
+            }
+            previous_runs.push(run);
             continue
         }
+        previous_runs.push(run);
 
         n += 1;
 
-        let time_ms = run
-            .times_ms()
-            .get(game.primary_timing())
-            .expect("run missing primary timing");
         let rank = u64::try_from(n).unwrap();
         let mut tied_rank = rank;
         let mut is_tied = false;
@@ -65,16 +144,396 @@ pub fn leaderboard(runs: &[Linked<Run>]) -> Vec<LeaderboardRun> {
             }
         }
 
+        best_ranks.insert(run.players(), tied_rank);
+
         let new = LeaderboardRun {
             rank,
             time_ms,
             is_tied,
             tied_rank,
+            is_obsolete: false,
+            best_rank: tied_rank,
+            superseded_by: None,
+            previous_rank: None,
             run: run.clone(),
         };
 
         leaderboard.push(new);
     }
 
+    if obsolete.is_empty() {
+        return leaderboard
+    }
+
+    for run in obsolete.iter_mut() {
+        let preceding = leaderboard
+            .iter()
+            .take_while(|ranked| order(ranked) <= order(run))
+            .count();
+        let faster = leaderboard
+            .iter()
+            .take_while(|ranked| ranked.time_ms < run.time_ms)
+            .count();
+        run.rank = u64::try_from(preceding + 1).unwrap();
+        run.tied_rank = u64::try_from(faster + 1).unwrap();
+        run.is_tied = leaderboard
+            .get(faster)
+            .map_or(false, |ranked| ranked.time_ms == run.time_ms);
+    }
+
+    leaderboard.extend(obsolete);
+    leaderboard.sort_by_key(order);
     leaderboard
 }
+
+/// Records each runner's rank on the `previous` leaderboard, typically the same
+/// one as of an earlier date, on their runs in `leaderboard`.
+pub fn compare_ranks(leaderboard: &mut [LeaderboardRun], previous: &[LeaderboardRun]) {
+    let previous_ranks: HashMap<&Vec<RunPlayer>, u64> = previous
+        .iter()
+        .filter(|ranked| !ranked.is_obsolete)
+        .map(|ranked| (ranked.run.players(), ranked.tied_rank))
+        .collect();
+
+    for ranked in leaderboard.iter_mut() {
+        ranked.previous_rank = previous_ranks.get(ranked.run.players()).copied();
+    }
+}
+
+/// The date a run was performed, or else submitted, if either is known.
+pub fn performed_on(run: &Run) -> Option<NaiveDate> {
+    run.date()
+        .or_else(|| run.created().map(|created| created.naive_utc().date()))
+}
+
+/// The earliest of a runner's `previous_runs` that's faster than their `run`
+/// with time `time_ms` and was performed on or after it, if any.
+fn superseded_by(
+    run: &Run,
+    time_ms: u64,
+    previous_runs: &[&Linked<Run>],
+    timing: &TimingMethod,
+) -> Option<Linked<Run>> {
+    let performed = performed_on(run)?;
+    previous_runs
+        .iter()
+        .filter(|previous| {
+            previous
+                .times_ms()
+                .get(timing)
+                .map_or(false, |previous_ms| previous_ms < time_ms)
+        })
+        .filter_map(|previous| {
+            performed_on(previous)
+                .filter(|date| *date >= performed)
+                .map(|date| ((date, *previous.created()), *previous))
+        })
+        .min_by_key(|(order, _)| *order)
+        .map(|(_, previous)| previous.clone())
+}
+
+/// The order runs are ranked in.
+fn order(ranked: &LeaderboardRun) -> (u64, Option<NaiveDate>, Option<DateTime<Utc>>) {
+    (ranked.time_ms, *ranked.run.date(), *ranked.run.created())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::data::fixtures::{
+        self, category, game, user, value, variable, TablesBuilder,
+    };
+
+    fn run(id: u64, user_id: u64, rta: u64, values: &[(u64, u64)]) -> Run {
+        Run {
+            times_ms: RunTimesMs {
+                igt:    if id % 3 == 0 { Some(rta / 2) } else { None },
+                rta:    Some(rta),
+                rta_nl: None,
+            },
+            values: values.iter().cloned().collect(),
+            platform_id: Some(20),
+            emulated: id % 2 == 1,
+            ..fixtures::run(id, user_id, rta)
+        }
+    }
+
+    /// The given runs by users 5 to 7 of a game on the NES.
+    fn tables(runs: Vec<Run>) -> TablesBuilder {
+        fixtures::tables()
+            .runs(runs)
+            .users((5..=7).map(user).collect())
+            .games(vec![Game {
+                platform_ids: vec![20],
+                ..game(1)
+            }])
+            .categories(vec![category(2, 1)])
+            .platforms(vec![Platform {
+                id:       20,
+                slug:     "nes".to_string(),
+                name:     "NES".to_string(),
+                released: 1983,
+            }])
+    }
+
+    #[test]
+    fn test_leaderboard_within_subcategory() {
+        let database = tables(vec![
+            Run {
+                date: Some(NaiveDate::from_ymd(2019, 2, 1)),
+                ..run(10, 5, 100, &[(3, 4)])
+            },
+            run(11, 6, 200, &[(3, 8)]),
+            Run {
+                date: Some(NaiveDate::from_ymd(2019, 1, 1)),
+                ..run(12, 5, 300, &[(3, 8)])
+            },
+            run(13, 7, 400, &[]),
+        ])
+        .variables(vec![Variable {
+            scope: VariableScope::FullGame,
+            slug: "glitches".to_string(),
+            name: "Glitches".to_string(),
+            mandatory: true,
+            default_value_id: Some(8),
+            ..variable(3, 2, vec![value(4, "Glitched"), value(8, "Glitchless")])
+        }])
+        .database();
+        let runs = database.runs_by_category_id_and_level_id(2, None);
+
+        let ids = |values: &[(u64, u64)]| -> Vec<u64> {
+            leaderboard(
+                &runs,
+                &values.iter().cloned().collect::<BTreeMap<_, _>>(),
+                &SystemFilter::default(),
+                &TimingMethod::RTA,
+                false,
+                None,
+            )
+            .iter()
+            .map(|ranked| *ranked.run().id())
+            .collect()
+        };
+
+        assert_eq!(ids(&[]), vec![10, 11, 13]);
+        assert_eq!(ids(&[(3, 4)]), vec![10]);
+        // runs without a value for the variable have its default value
+        assert_eq!(ids(&[(3, 8)]), vec![11, 12, 13]);
+        assert_eq!(ids(&[(3, 9)]), Vec::<u64>::new());
+
+        let history = leaderboard(
+            &runs,
+            &BTreeMap::new(),
+            &SystemFilter::default(),
+            &TimingMethod::RTA,
+            true,
+            None,
+        );
+        assert_eq!(
+            history
+                .iter()
+                .map(|ranked| (*ranked.run().id(), *ranked.is_obsolete()))
+                .collect::<Vec<_>>(),
+            vec![(10, false), (11, false), (12, true), (13, false)]
+        );
+        let obsolete = &history[2];
+        assert_eq!(*obsolete.rank(), 3);
+        assert_eq!(*obsolete.best_rank(), 1);
+        assert_eq!(
+            obsolete.superseded_by().as_ref().map(|run| *run.id()),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn test_superseded_by() {
+        let day = |month| Some(NaiveDate::from_ymd(2019, month, 1));
+        let database = tables(vec![
+            Run {
+                date: day(1),
+                ..run(10, 5, 300, &[])
+            },
+            Run {
+                date: day(3),
+                ..run(11, 5, 200, &[])
+            },
+            Run {
+                date: day(2),
+                ..run(12, 5, 100, &[])
+            },
+            // performed after all of the runner's faster runs
+            Run {
+                date: day(4),
+                ..run(13, 5, 400, &[])
+            },
+            // performed, and submitted, at an unknown time
+            run(14, 5, 500, &[]),
+        ])
+        .database();
+        let runs = database.runs_by_category_id_and_level_id(2, None);
+
+        assert_eq!(
+            leaderboard(
+                &runs,
+                &BTreeMap::new(),
+                &SystemFilter::default(),
+                &TimingMethod::RTA,
+                true,
+                None,
+            )
+            .iter()
+            .map(|ranked| (
+                *ranked.run().id(),
+                ranked.superseded_by().as_ref().map(|run| *run.id())
+            ))
+            .collect::<Vec<_>>(),
+            vec![
+                (12, None),
+                (11, None),
+                (10, Some(12)),
+                (13, None),
+                (14, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leaderboard_by_system() {
+        let database = tables(vec![
+            run(10, 5, 100, &[]),
+            run(11, 6, 200, &[]),
+            run(12, 7, 300, &[]),
+        ])
+        .database();
+        let runs = database.runs_by_category_id_and_level_id(2, None);
+
+        let ids = |system: SystemFilter| -> Vec<u64> {
+            leaderboard(
+                &runs,
+                &BTreeMap::new(),
+                &system,
+                &TimingMethod::RTA,
+                false,
+                None,
+            )
+            .iter()
+            .map(|ranked| *ranked.run().id())
+            .collect()
+        };
+
+        assert_eq!(
+            ids(SystemFilter {
+                emulated: Some(false),
+                ..SystemFilter::default()
+            }),
+            vec![10, 12]
+        );
+        assert_eq!(
+            ids(SystemFilter {
+                emulated: Some(true),
+                ..SystemFilter::default()
+            }),
+            vec![11]
+        );
+        assert_eq!(
+            ids(SystemFilter {
+                platform_id: Some(20),
+                ..SystemFilter::default()
+            }),
+            vec![10, 11, 12]
+        );
+        assert_eq!(
+            ids(SystemFilter {
+                region_id: Some(30),
+                ..SystemFilter::default()
+            }),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    fn test_leaderboard_by_timing() {
+        let database = tables(vec![
+            run(11, 5, 100, &[]),
+            run(12, 6, 300, &[]),
+            run(15, 7, 200, &[]),
+        ])
+        .database();
+        let runs = database.runs_by_category_id_and_level_id(2, None);
+
+        let ids = |timing: TimingMethod| -> Vec<u64> {
+            leaderboard(
+                &runs,
+                &BTreeMap::new(),
+                &SystemFilter::default(),
+                &timing,
+                false,
+                None,
+            )
+            .iter()
+            .map(|ranked| *ranked.run().id())
+            .collect()
+        };
+
+        assert_eq!(ids(TimingMethod::RTA), vec![11, 15, 12]);
+        // runs without an in-game time are left off of the IGT leaderboard
+        assert_eq!(ids(TimingMethod::IGT), vec![15, 12]);
+        assert_eq!(ids(TimingMethod::RTA_NL), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_leaderboard_as_of() {
+        let day = |year, month, day| NaiveDate::from_ymd(year, month, day);
+        let database = tables(vec![
+            Run {
+                date: Some(day(2019, 1, 1)),
+                ..run(10, 5, 300, &[])
+            },
+            Run {
+                date: Some(day(2019, 6, 1)),
+                ..run(11, 6, 200, &[])
+            },
+            Run {
+                date: Some(day(2020, 1, 1)),
+                ..run(12, 5, 100, &[])
+            },
+            // only the submission date is known for this run
+            Run {
+                created: Some(Utc.ymd(2019, 3, 1).and_hms(0, 0, 0)),
+                ..run(13, 7, 250, &[])
+            },
+        ])
+        .database();
+        let runs = database.runs_by_category_id_and_level_id(2, None);
+        let board = |as_of: Option<NaiveDate>| -> Vec<LeaderboardRun> {
+            leaderboard(
+                &runs,
+                &BTreeMap::new(),
+                &SystemFilter::default(),
+                &TimingMethod::RTA,
+                false,
+                as_of,
+            )
+        };
+        let ids = |board: &[LeaderboardRun]| -> Vec<u64> {
+            board.iter().map(|ranked| *ranked.run().id()).collect()
+        };
+
+        let previous = board(Some(day(2019, 12, 31)));
+        assert_eq!(ids(&previous), vec![11, 13, 10]);
+        assert_eq!(ids(&board(Some(day(2019, 2, 1)))), vec![10]);
+
+        let mut current = board(None);
+        assert_eq!(ids(&current), vec![12, 11, 13]);
+        compare_ranks(&mut current, &previous);
+        assert_eq!(
+            current
+                .iter()
+                .map(|ranked| ranked.rank_change())
+                .collect::<Vec<_>>(),
+            vec![Some(2), Some(-1), Some(-1)]
+        );
+    }
+}
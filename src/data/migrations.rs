@@ -0,0 +1,351 @@
+//! Versioning of the normalized JSONL table format, and migrations to upgrade
+//! records written by older versions.
+//!
+//! The normalized types deny unknown fields, so a record written before or
+//! after a model change won't deserialize as-is. Instead, the version of the
+//! format is recorded in a `manifest.json` next to the tables, and records from
+//! older versions are upgraded as JSON values by the [MIGRATIONS] registry
+//! before they're deserialized.
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{prelude::*, BufReader, BufWriter},
+    path::Path,
+};
+
+use err_derive::Error;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tempfile::NamedTempFile;
+
+/// The version of the normalized table format written by this build. This must
+/// be incremented whenever the serialized form of a model in
+/// [crate::data::types] changes, with a corresponding entry in [MIGRATIONS].
+pub const FORMAT_VERSION: u32 = 8;
+
+/// Every migration, in the order they must be applied.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from_version: 1,
+        table:        "runs",
+        description:  "add empty variable values",
+        migrate:      add_run_values,
+    },
+    Migration {
+        from_version: 2,
+        table:        "runs",
+        description:  "add empty videos and comment",
+        migrate:      add_run_videos_and_comment,
+    },
+    Migration {
+        from_version: 3,
+        table:        "runs",
+        description:  "add unknown platform and region, not emulated",
+        migrate:      add_run_system,
+    },
+    Migration {
+        from_version: 3,
+        table:        "games",
+        description:  "add empty platforms and regions",
+        migrate:      add_game_platforms_and_regions,
+    },
+    Migration {
+        from_version: 4,
+        table:        "users",
+        description:  "add unknown location, no links, plain role and style",
+        migrate:      add_user_profile,
+    },
+    Migration {
+        from_version: 5,
+        table:        "games",
+        description:  "add unknown release date, no tags or assets, not a romhack",
+        migrate:      add_game_metadata,
+    },
+    Migration {
+        from_version: 7,
+        table:        "runs",
+        description:  "add unknown examiner and verification time",
+        migrate:      add_run_verification,
+    },
+    Migration {
+        from_version: 7,
+        table:        "games",
+        description:  "add no moderators",
+        migrate:      add_game_moderators,
+    },
+];
+
+fn add_run_values(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("values".to_string(), JsonValue::Object(Default::default()));
+}
+
+fn add_run_videos_and_comment(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("videos".to_string(), JsonValue::Array(vec![]));
+    record.insert("comment".to_string(), JsonValue::Null);
+}
+
+fn add_run_system(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("platform_id".to_string(), JsonValue::Null);
+    record.insert("region_id".to_string(), JsonValue::Null);
+    record.insert("emulated".to_string(), JsonValue::Bool(false));
+}
+
+fn add_game_platforms_and_regions(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("platform_ids".to_string(), JsonValue::Array(vec![]));
+    record.insert("region_ids".to_string(), JsonValue::Array(vec![]));
+}
+
+fn add_user_profile(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("location".to_string(), JsonValue::Null);
+    record.insert(
+        "links".to_string(),
+        serde_json::json!({
+            "twitch": null,
+            "youtube": null,
+            "twitter": null,
+            "hitbox": null,
+            "speedrunslive": null,
+        }),
+    );
+    record.insert("role".to_string(), JsonValue::String("User".to_string()));
+    record.insert("name_style".to_string(), JsonValue::Null);
+}
+
+fn add_game_metadata(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("release_date".to_string(), JsonValue::Null);
+    record.insert("romhack".to_string(), JsonValue::Bool(false));
+    record.insert("tag_ids".to_string(), JsonValue::Array(vec![]));
+    record.insert(
+        "assets".to_string(),
+        serde_json::json!({
+            "cover": null,
+            "icon": null,
+            "logo": null,
+            "background": null,
+        }),
+    );
+}
+
+fn add_run_verification(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("examiner_id".to_string(), JsonValue::Null);
+    record.insert("verified".to_string(), JsonValue::Null);
+}
+
+fn add_game_moderators(record: &mut JsonMap<String, JsonValue>) {
+    record.insert("moderators".to_string(), JsonValue::Array(vec![]));
+}
+
+/// Upgrades records of one table from one format version to the next.
+pub struct Migration {
+    /// The version that records are upgraded from, to this version plus one.
+    pub from_version: u32,
+    /// The name of the table whose records are upgraded, such as `"runs"`.
+    pub table:        &'static str,
+    pub description:  &'static str,
+    pub migrate:      fn(&mut JsonMap<String, JsonValue>),
+}
+
+impl Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Migration")
+            .field("from_version", &self.from_version)
+            .field("table", &self.table)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error(display = "manifest I/O failed: {}", _0)]
+    Io(std::io::Error),
+    #[error(display = "manifest JSON invalid: {}", _0)]
+    Json(serde_json::Error),
+    #[error(
+        display = "normalized data is format version {}, but this build only supports up \
+                   to version {}; rebuild with newer code or re-normalize the data",
+        found,
+        supported
+    )]
+    TooNew { found: u32, supported: u32 },
+    #[error(display = "{} record to migrate is not a JSON object", table)]
+    NotAnObject { table: &'static str },
+}
+
+impl From<std::io::Error> for MigrationError {
+    fn from(error: std::io::Error) -> Self {
+        MigrationError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for MigrationError {
+    fn from(error: serde_json::Error) -> Self {
+        MigrationError::Json(error)
+    }
+}
+
+/// Describes a directory of normalized tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub version: u32,
+}
+
+impl Manifest {
+    /// The manifest for tables written by this build.
+    pub fn current() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+        }
+    }
+
+    /// Reads the manifest from a directory of normalized tables, verifying that
+    /// this build can read them. Tables written before manifests existed are
+    /// treated as version 1.
+    pub fn read(dir: impl AsRef<Path>) -> Result<Self, MigrationError> {
+        let path = dir.as_ref().join("manifest.json");
+        let manifest = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                warn!("{:?} not found, assuming format version 1.", path);
+                Manifest { version: 1 }
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        if manifest.version > FORMAT_VERSION {
+            return Err(MigrationError::TooNew {
+                found:     manifest.version,
+                supported: FORMAT_VERSION,
+            })
+        }
+
+        Ok(manifest)
+    }
+
+    /// Writes this manifest to a directory of normalized tables, atomically
+    /// replacing any existing one.
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<(), MigrationError> {
+        let dir = dir.as_ref();
+        let mut file = NamedTempFile::new_in(dir)?;
+        {
+            let mut buffer = BufWriter::new(&mut file);
+            serde_json::to_writer_pretty(&mut buffer, self)?;
+            buffer.write_all(b"\n")?;
+        }
+        file.persist(dir.join("manifest.json"))
+            .map_err(|error| error.error)?;
+
+        Ok(())
+    }
+
+    /// Upgrades a record of the given table from this manifest's version to
+    /// the current version.
+    pub fn migrate(
+        &self,
+        table: &str,
+        record: &mut JsonValue,
+    ) -> Result<(), MigrationError> {
+        migrate_with(MIGRATIONS, self.version, table, record)
+    }
+}
+
+fn migrate_with(
+    migrations: &[Migration],
+    version: u32,
+    table: &str,
+    record: &mut JsonValue,
+) -> Result<(), MigrationError> {
+    for migration in migrations {
+        if migration.from_version >= version && migration.table == table {
+            let record = record.as_object_mut().ok_or(MigrationError::NotAnObject {
+                table: migration.table,
+            })?;
+            (migration.migrate)(record);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn add_videos(record: &mut JsonMap<String, JsonValue>) {
+        record.insert("videos".to_string(), json!([]));
+    }
+
+    fn rename_time(record: &mut JsonMap<String, JsonValue>) {
+        let time = record.remove("time").expect("record has time");
+        record.insert("time_ms".to_string(), time);
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            from_version: 1,
+            table:        "runs",
+            description:  "add videos",
+            migrate:      add_videos,
+        },
+        Migration {
+            from_version: 2,
+            table:        "runs",
+            description:  "rename time to time_ms",
+            migrate:      rename_time,
+        },
+        Migration {
+            from_version: 2,
+            table:        "games",
+            description:  "unrelated table",
+            migrate:      add_videos,
+        },
+    ];
+
+    #[test]
+    fn test_migrate_applies_later_migrations_in_order() {
+        let mut record = json!({"id": 1, "time": 1234});
+        migrate_with(TEST_MIGRATIONS, 1, "runs", &mut record).expect("record migrated");
+        assert_eq!(record, json!({"id": 1, "time_ms": 1234, "videos": []}));
+
+        let mut record = json!({"id": 1, "time": 1234, "videos": ["a"]});
+        migrate_with(TEST_MIGRATIONS, 2, "runs", &mut record).expect("record migrated");
+        assert_eq!(record, json!({"id": 1, "time_ms": 1234, "videos": ["a"]}));
+
+        let mut record = json!({"id": 1, "time_ms": 1234});
+        migrate_with(TEST_MIGRATIONS, 3, "runs", &mut record).expect("record migrated");
+        assert_eq!(record, json!({"id": 1, "time_ms": 1234}));
+    }
+
+    #[test]
+    fn test_migrate_rejects_non_objects() {
+        let mut record = json!([1]);
+        match migrate_with(TEST_MIGRATIONS, 1, "runs", &mut record) {
+            Err(MigrationError::NotAnObject { table }) => assert_eq!(table, "runs"),
+            other => panic!("expected not an object, got {:?}", other),
+        }
+
+        // records that don't need migrating are left for deserialization to reject
+        migrate_with(TEST_MIGRATIONS, 3, "runs", &mut record).expect("nothing to migrate");
+    }
+
+    #[test]
+    fn test_manifest_too_new() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        Manifest {
+            version: FORMAT_VERSION + 1,
+        }
+        .write(dir.path())
+        .expect("manifest written");
+
+        match Manifest::read(dir.path()) {
+            Err(MigrationError::TooNew { found, supported }) => {
+                assert_eq!(found, FORMAT_VERSION + 1);
+                assert_eq!(supported, FORMAT_VERSION);
+            }
+            other => panic!("expected too new, got {:?}", other),
+        }
+    }
+}
@@ -1,7 +1,21 @@
 //! Our speedrun data model.
 pub mod database;
+pub mod diff;
+#[cfg(test)]
+pub(crate) mod fixtures;
 pub mod graphql;
+pub mod jsonl;
 pub mod leaderboard;
+pub mod migrations;
 pub mod models;
+pub mod moderation;
+pub mod personal_bests;
 pub mod progression;
+pub mod query;
+pub mod repair;
+pub mod search;
+pub mod shards;
+pub mod snapshot;
+pub mod sqlite;
+pub mod sum_of_best;
 pub mod types;
@@ -46,6 +46,12 @@ pub enum AnyModel {
     Game(Game),
     Category(Category),
     Level(Level),
+    Variable(Variable),
+    Platform(Platform),
+    Region(Region),
+    GameTag(GameTag),
+    GameRelation(GameRelation),
+    UnverifiedRun(UnverifiedRun),
 }
 
 /// A reference to a homogenous Vec of any Model type.
@@ -68,6 +74,31 @@ pub enum AnyModelVec {
     Games(Vec<Game>),
     Categories(Vec<Category>),
     Levels(Vec<Level>),
+    Variables(Vec<Variable>),
+    Platforms(Vec<Platform>),
+    Regions(Vec<Region>),
+    GameTags(Vec<GameTag>),
+    GameRelations(Vec<GameRelation>),
+    UnverifiedRuns(Vec<UnverifiedRun>),
+}
+
+impl AnyModel {
+    /// The name of the table this record belongs in.
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            AnyModel::Run(_) => "runs",
+            AnyModel::User(_) => "users",
+            AnyModel::Game(_) => "games",
+            AnyModel::Category(_) => "categories",
+            AnyModel::Level(_) => "levels",
+            AnyModel::Variable(_) => "variables",
+            AnyModel::Platform(_) => "platforms",
+            AnyModel::Region(_) => "regions",
+            AnyModel::GameTag(_) => "game_tags",
+            AnyModel::GameRelation(_) => "game_relations",
+            AnyModel::UnverifiedRun(_) => "unverified_runs",
+        }
+    }
 }
 
 impl Model for AnyModel {
@@ -78,6 +109,12 @@ impl Model for AnyModel {
             AnyModel::Game(game) => Model::id(game),
             AnyModel::Category(category) => Model::id(category),
             AnyModel::Level(level) => Model::id(level),
+            AnyModel::Variable(variable) => Model::id(variable),
+            AnyModel::Platform(platform) => Model::id(platform),
+            AnyModel::Region(region) => Model::id(region),
+            AnyModel::GameTag(tag) => Model::id(tag),
+            AnyModel::GameRelation(relation) => Model::id(relation),
+            AnyModel::UnverifiedRun(run) => Model::id(run),
         }
     }
 
@@ -88,6 +125,12 @@ impl Model for AnyModel {
             AnyModel::Game(game) => Model::created(game),
             AnyModel::Category(category) => Model::created(category),
             AnyModel::Level(level) => Model::created(level),
+            AnyModel::Variable(variable) => Model::created(variable),
+            AnyModel::Platform(platform) => Model::created(platform),
+            AnyModel::Region(region) => Model::created(region),
+            AnyModel::GameTag(tag) => Model::created(tag),
+            AnyModel::GameRelation(relation) => Model::created(relation),
+            AnyModel::UnverifiedRun(run) => Model::created(run),
         }
     }
 }
@@ -141,3 +184,65 @@ impl Model for Level {
         None
     }
 }
+
+impl Model for Variable {
+    fn id(&self) -> u64 {
+        *Variable::id(self)
+    }
+
+    fn created(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl Model for Platform {
+    fn id(&self) -> u64 {
+        *Platform::id(self)
+    }
+
+    fn created(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl Model for Region {
+    fn id(&self) -> u64 {
+        *Region::id(self)
+    }
+
+    fn created(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl Model for GameTag {
+    fn id(&self) -> u64 {
+        *GameTag::id(self)
+    }
+
+    fn created(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+/// Identified by the ID of the game it belongs to, since each game has at most
+/// one.
+impl Model for GameRelation {
+    fn id(&self) -> u64 {
+        *GameRelation::game_id(self)
+    }
+
+    fn created(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl Model for UnverifiedRun {
+    fn id(&self) -> u64 {
+        *self.run.id()
+    }
+
+    fn created(&self) -> Option<DateTime<Utc>> {
+        *self.run.created()
+    }
+}
@@ -0,0 +1,129 @@
+//! Statistics on how a game's runs are being moderated: its queue of pending
+//! runs, how long verification takes, and who rejects runs.
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use getset::Getters;
+use serde::Serialize;
+
+use crate::data::{database::Linked, types::*};
+
+/// How a game's runs have been moderated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Getters, Serialize)]
+#[get = "pub"]
+pub struct ModerationStats {
+    /// The number of runs waiting to be verified or rejected.
+    queue_size:                usize,
+    /// The mean time from a run being submitted to it being verified, over the
+    /// verified runs where both times are known.
+    average_verification_ms:   Option<u64>,
+    /// The number of runs each moderator has rejected, by user ID.
+    rejections_by_examiner_id: BTreeMap<u64, usize>,
+}
+
+/// Summarizes the moderation of a game's verified and unverified `runs`.
+pub fn moderation_stats(
+    runs: &[Linked<Run>],
+    unverified_runs: &[Linked<UnverifiedRun>],
+) -> ModerationStats {
+    let mut stats = ModerationStats::default();
+
+    let verification_times: Vec<u64> = runs
+        .iter()
+        .filter_map(|run| {
+            let duration = run.verified?.signed_duration_since(run.created?);
+            u64::try_from(duration.num_milliseconds()).ok()
+        })
+        .collect();
+    if !verification_times.is_empty() {
+        let total_ms: u64 = verification_times.iter().sum();
+        let count = u64::try_from(verification_times.len()).expect("run count fits in u64");
+        stats.average_verification_ms = Some(total_ms / count);
+    }
+
+    for unverified in unverified_runs {
+        match unverified.status {
+            ModerationStatus::Pending => stats.queue_size += 1,
+            ModerationStatus::Rejected =>
+                if let Some(examiner_id) = unverified.run.examiner_id {
+                    *stats
+                        .rejections_by_examiner_id
+                        .entry(examiner_id)
+                        .or_default() += 1;
+                },
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::*;
+    use crate::data::fixtures::{category, game, run, tables};
+
+    fn timed_run(id: u64, created_hour: u32, verified_hour: Option<u32>) -> Run {
+        let hour = |hour: u32| -> DateTime<Utc> { Utc.ymd(2020, 1, 1).and_hms(hour, 0, 0) };
+        Run {
+            created: Some(hour(created_hour)),
+            players: vec![RunPlayer::GuestName(format!("runner {}", id))],
+            examiner_id: verified_hour.map(|_| 30),
+            verified: verified_hour.map(hour),
+            ..run(id, 0, 1000)
+        }
+    }
+
+    fn unverified(id: u64, examiner_id: Option<u64>) -> UnverifiedRun {
+        UnverifiedRun {
+            run:    Run {
+                examiner_id,
+                ..timed_run(id, 0, None)
+            },
+            status: if examiner_id.is_some() {
+                ModerationStatus::Rejected
+            } else {
+                ModerationStatus::Pending
+            },
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_moderation_stats() {
+        let database = tables()
+            .runs(vec![
+                timed_run(10, 0, Some(1)),
+                timed_run(11, 0, Some(3)),
+                timed_run(12, 0, None),
+            ])
+            .games(vec![Game {
+                moderators: vec![GameModerator {
+                    user_id: 30,
+                    kind:    ModeratorKind::SuperModerator,
+                }],
+                ..game(1)
+            }])
+            .categories(vec![category(2, 1)])
+            .unverified_runs(vec![
+                unverified(20, None),
+                unverified(21, None),
+                unverified(22, Some(30)),
+                unverified(23, Some(31)),
+                unverified(24, Some(30)),
+            ])
+            .database();
+        let game = database.game_by_id(1).expect("game exists");
+
+        let stats = moderation_stats(&game.runs(), &game.unverified_runs());
+
+        assert_eq!(*stats.queue_size(), 2);
+        assert_eq!(*stats.average_verification_ms(), Some(2 * 60 * 60 * 1000));
+        assert_eq!(
+            stats.rejections_by_examiner_id(),
+            &vec![(30, 2), (31, 1)]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! A user's best run on every leaderboard they've run, with where it ranks.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use getset::Getters;
+use serde::Serialize;
+
+use crate::data::{
+    database::Database,
+    leaderboard::{leaderboard, LeaderboardRun, SystemFilter},
+    types::*,
+};
+
+#[derive(Debug, Clone, Getters, Serialize)]
+#[get = "pub"]
+pub struct PersonalBest {
+    /// The user's best run on the leaderboard, with its rank.
+    leaderboard_run: LeaderboardRun,
+    /// How many runs are ranked on the leaderboard.
+    board_size:      usize,
+}
+
+/// Finds the given user's best run on each leaderboard (game, category, level,
+/// and subcategory) they have a verified run on in `database`, ranked by the
+/// game's primary timing method, sorted by category and level ID.
+pub fn personal_bests(database: &Arc<Database>, user_id: u64) -> Vec<PersonalBest> {
+    let player = RunPlayer::UserId(user_id);
+
+    let boards: BTreeSet<(u64, Option<u64>, BTreeMap<u64, u64>)> = database
+        .runs_by_user_id(user_id)
+        .iter()
+        .map(|run| {
+            (
+                *run.category_id(),
+                *run.level_id(),
+                run.subcategory_values(),
+            )
+        })
+        .collect();
+
+    let mut personal_bests = vec![];
+    for (category_id, level_id, values) in boards {
+        let category = database
+            .category_by_id(category_id)
+            .expect("run's category exists");
+        let level = level_id
+            .map(|level_id| database.level_by_id(level_id).expect("run's level exists"));
+        let runs = database
+            .query_runs()
+            .category(&category)
+            .level(level.as_deref())
+            .without_banned_users()
+            .to_vec();
+
+        let ranked = leaderboard(
+            &runs,
+            &values,
+            &SystemFilter::default(),
+            category.game().primary_timing(),
+            false,
+            None,
+        );
+        if let Some(best) = ranked
+            .iter()
+            .find(|ranked| ranked.run().players().contains(&player))
+        {
+            personal_bests.push(PersonalBest {
+                leaderboard_run: best.clone(),
+                board_size:      ranked.len(),
+            });
+        }
+    }
+    personal_bests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{category, game, run, tables, user};
+
+    #[test]
+    fn test_personal_bests() {
+        let database = tables()
+            .runs(vec![run(10, 5, 100), run(11, 6, 200), run(12, 5, 300)])
+            .users((5..=7).map(user).collect())
+            .games(vec![game(1)])
+            .categories(vec![category(2, 1)])
+            .database();
+
+        let summary = |user_id: u64| -> Vec<(u64, u64, usize)> {
+            personal_bests(&database, user_id)
+                .iter()
+                .map(|best| {
+                    (
+                        *best.leaderboard_run().run().id(),
+                        *best.leaderboard_run().tied_rank(),
+                        *best.board_size(),
+                    )
+                })
+                .collect()
+        };
+
+        assert_eq!(summary(5), vec![(10, 1, 2)]);
+        assert_eq!(summary(6), vec![(11, 2, 2)]);
+        assert_eq!(summary(7), vec![]);
+    }
+}
@@ -1,45 +1,163 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
+
+use chrono::NaiveDate;
 use getset::Getters;
 use serde::Serialize;
 
 use crate::data::{
     database::Linked,
-    leaderboard::{leaderboard, LeaderboardRun},
+    leaderboard::{leaderboard, performed_on, LeaderboardRun, SystemFilter},
     types::*,
 };
 
 #[derive(Debug, Clone, Getters, Serialize)]
 #[get = "pub"]
 pub struct ProgressionRun {
+    /// How much faster this run was than the previous record, or 0 for the
+    /// first record and for ties.
     improvement_ms:  u64,
+    /// Whether this run tied the record rather than beating it.
+    is_tie:          bool,
+    /// How many days passed before the record was beaten, or None if it
+    /// still stands.
+    days_standing:   Option<u64>,
     run:             Linked<Run>,
-    leaderboard_run: LeaderboardRun,
+    /// This run's place on the current leaderboard, unless it's obsolete.
+    leaderboard_run: Option<LeaderboardRun>,
 }
 
-pub fn progression(runs: &[Linked<Run>]) -> Vec<ProgressionRun> {
-    let leaderboard_runs = leaderboard(runs);
+/// Finds the runs (all for the same game/category/level) that set a new
+/// record by the given `timing` method, in the order they were performed.
+/// Runs tying the record are included if `include_ties` is set.
+///
+/// Only runs with every one of the given variable `values` (value IDs by
+/// variable ID) count, as on [leaderboard]s. Runs without a date are treated
+/// as performed when they were submitted, and skipped if that isn't known
+/// either.
+pub fn progression(
+    runs: &[Linked<Run>],
+    values: &BTreeMap<u64, u64>,
+    timing: &TimingMethod,
+    include_ties: bool,
+) -> Vec<ProgressionRun> {
+    let mut leaderboard_runs: HashMap<u64, LeaderboardRun> =
+        leaderboard(runs, values, &SystemFilter::default(), timing, false, None)
+            .into_iter()
+            .map(|ranked| (*ranked.run().id(), ranked))
+            .collect();
+
+    let mut runs: Vec<(NaiveDate, u64, &Linked<Run>)> = runs
+        .iter()
+        .filter(|run| run.has_values(values))
+        .filter_map(|run| Some((performed_on(run)?, run.times_ms().get(timing)?, run)))
+        .collect();
+
+    runs.sort_by_key(|(date, _, run)| (*date, *run.created(), *run.id()));
 
-    leaderboard_runs
-        .into_iter()
-        .map(|lr| ProgressionRun {
-            improvement_ms:  0,
-            run:             lr.run().clone(),
-            leaderboard_run: lr,
-        })
-        .collect()
+    let mut progression: Vec<ProgressionRun> = vec![];
+    let mut record_ms: Option<u64> = None;
+    // the indices of the current record and its ties, and when they were set
+    let mut standing: Vec<(usize, NaiveDate)> = vec![];
 
-    // let runs: Vec<Linked<Run>> = runs.to_vec();
+    for (date, time_ms, run) in runs {
+        let (improvement_ms, is_tie) = match record_ms {
+            None => (0, false),
+            Some(record_ms) if time_ms < record_ms => (record_ms - time_ms, false),
+            Some(record_ms) if time_ms == record_ms && include_ties => (0, true),
+            Some(_) => continue,
+        };
 
-    // if runs.is_empty() {
-    //     return vec![];
-    // }
+        if !is_tie {
+            for (index, set_on) in standing.drain(..) {
+                let days = date.signed_duration_since(set_on).num_days();
+                progression[index].days_standing =
+                    Some(u64::try_from(days).expect("runs are sorted by date"));
+            }
+            record_ms = Some(time_ms);
+        }
+
+        standing.push((progression.len(), date));
+
+        progression.push(ProgressionRun {
+            improvement_ms,
+            is_tie,
+            days_standing: None,
+            run: run.clone(),
+            leaderboard_run: leaderboard_runs.remove(run.id()),
+        });
+    }
+
+    progression
+}
 
-    // let mut progression: Vec<ProgressionRun> = vec![];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{category, game, run, tables, user};
 
-    // for run in runs.iter() {
-    //     // let new = ProgressionRun { run: run.clone() };
+    #[test]
+    fn test_progression() {
+        let day = |month, day| Some(NaiveDate::from_ymd(2019, month, day));
+        let dated = |id, user_id, rta_ms, date| Run {
+            date,
+            ..run(id, user_id, rta_ms)
+        };
+        let database = tables()
+            .runs(vec![
+                dated(10, 5, 300, day(1, 1)),
+                dated(11, 6, 350, day(2, 1)),
+                dated(12, 6, 300, day(3, 1)),
+                dated(13, 7, 250, day(5, 1)),
+                dated(14, 5, 200, day(6, 10)),
+                // runs without any date can't be placed in the progression
+                run(15, 8, 100),
+            ])
+            .users((5..=8).map(user).collect())
+            .games(vec![game(1)])
+            .categories(vec![category(2, 1)])
+            .database();
+        let runs = database.runs_by_category_id_and_level_id(2, None);
 
-    //     // progression.push(new);
-    // }
+        let records = progression(&runs, &BTreeMap::new(), &TimingMethod::RTA, false);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| *record.run().id())
+                .collect::<Vec<_>>(),
+            vec![10, 13, 14]
+        );
 
-    // progression
+        let records = progression(&runs, &BTreeMap::new(), &TimingMethod::RTA, true);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (
+                    *record.run().id(),
+                    *record.improvement_ms(),
+                    *record.is_tie(),
+                    *record.days_standing(),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (10, 0, false, Some(120)),
+                (12, 0, true, Some(61)),
+                (13, 50, false, Some(40)),
+                (14, 50, false, None),
+            ]
+        );
+        // the first record is obsolete, since its runner has since beaten it
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record
+                    .leaderboard_run()
+                    .as_ref()
+                    .map(|ranked| *ranked.rank()))
+                .collect::<Vec<_>>(),
+            vec![None, Some(4), Some(3), Some(2)]
+        );
+    }
 }
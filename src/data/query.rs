@@ -0,0 +1,426 @@
+//! Composable queries over the runs and games in a [Database], which use the
+//! most selective index available for the filters they're given.
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate};
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+
+use crate::data::{
+    database::{Database, Linked},
+    types::*,
+};
+
+/// A query for runs matching every filter that's been applied to it. Create
+/// one with [Database::query_runs].
+#[derive(Debug, Clone)]
+pub struct RunQuery {
+    database:    Arc<Database>,
+    game_id:     Option<u64>,
+    category_id: Option<u64>,
+    /// Some(None) only matches full-game runs, None matches any level.
+    level_id:    Option<Option<u64>>,
+    player:      Option<RunPlayer>,
+    dates:       Option<(NaiveDate, NaiveDate)>,
+    timing:      Option<TimingMethod>,
+    hide_banned: bool,
+}
+
+impl RunQuery {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            game_id: None,
+            category_id: None,
+            level_id: None,
+            player: None,
+            dates: None,
+            timing: None,
+            hide_banned: false,
+        }
+    }
+
+    /// Only matches runs of the given game.
+    pub fn game(mut self, game: &Game) -> Self {
+        self.game_id = Some(*game.id());
+        self
+    }
+
+    /// Only matches runs in the given category.
+    pub fn category(mut self, category: &Category) -> Self {
+        self.game_id = Some(*category.game_id());
+        self.category_id = Some(*category.id());
+        self
+    }
+
+    /// Only matches runs of the given level, or full-game runs if None.
+    pub fn level(mut self, level: Option<&Level>) -> Self {
+        if let Some(level) = level {
+            self.game_id = Some(*level.game_id());
+        }
+        self.level_id = Some(level.map(|level| *level.id()));
+        self
+    }
+
+    /// Only matches runs that the given player participated in.
+    pub fn player(mut self, player: RunPlayer) -> Self {
+        self.player = Some(player);
+        self
+    }
+
+    /// Only matches runs that the given user participated in.
+    pub fn user(self, user: &User) -> Self {
+        self.player(RunPlayer::UserId(*user.id()))
+    }
+
+    /// Only matches runs performed between the given dates, inclusive. Runs
+    /// with unknown dates are excluded.
+    pub fn date_between(mut self, start: NaiveDate, end: NaiveDate) -> Self {
+        self.dates = Some((start, end));
+        self
+    }
+
+    /// Only matches runs with a time for the given timing method.
+    pub fn timing(mut self, timing: TimingMethod) -> Self {
+        self.timing = Some(timing);
+        self
+    }
+
+    /// Excludes runs that any banned user participated in.
+    pub fn without_banned_users(mut self) -> Self {
+        self.hide_banned = true;
+        self
+    }
+
+    /// Returns whether a run matches every filter in this query.
+    pub fn matches(&self, run: &Run) -> bool {
+        self.game_id.map_or(true, |id| *run.game_id() == id)
+            && self.category_id.map_or(true, |id| *run.category_id() == id)
+            && self.level_id.map_or(true, |id| *run.level_id() == id)
+            && self
+                .player
+                .as_ref()
+                .map_or(true, |player| run.players().contains(player))
+            && self.dates.map_or(true, |(start, end)| match run.date() {
+                Some(date) => start <= *date && *date <= end,
+                None => false,
+            })
+            && self
+                .timing
+                .as_ref()
+                .map_or(true, |timing| run.times_ms().get(timing).is_some())
+            && (!self.hide_banned || !self.has_banned_user(run))
+    }
+
+    fn has_banned_user(&self, run: &Run) -> bool {
+        run.players().iter().any(|player| match player {
+            RunPlayer::UserId(user_id) => self
+                .database
+                .user_by_id(*user_id)
+                .map_or(false, |user| user.is_banned()),
+            RunPlayer::GuestName(_) => false,
+        })
+    }
+
+    /// Iterates over the matching runs, sorted by game, category, level, and
+    /// then ID.
+    pub fn iter(&self) -> impl Iterator<Item = Linked<Run>> + '_ {
+        self.candidates().filter(move |run| self.matches(run))
+    }
+
+    /// Returns a Vec of the matching runs, sorted by game, category, level,
+    /// and then ID.
+    pub fn to_vec(&self) -> Vec<Linked<Run>> {
+        self.iter().collect()
+    }
+
+    /// Returns a superset of the matching runs from the most selective index
+    /// that applies to this query.
+    fn candidates(&self) -> Box<dyn Iterator<Item = Linked<Run>> + '_> {
+        let database = &self.database;
+        match (self.category_id, self.level_id, &self.player, self.game_id) {
+            (Some(category_id), Some(level_id), _, _) => Box::new(
+                database
+                    .runs_by_category_id_and_level_id(category_id, level_id)
+                    .into_iter(),
+            ),
+            (_, _, Some(RunPlayer::UserId(user_id)), _) =>
+                Box::new(database.runs_by_user_id(*user_id).into_iter()),
+            (_, _, _, Some(game_id)) => Box::new(
+                database
+                    .runs_by_game_id(game_id)
+                    .unwrap_or_default()
+                    .into_iter(),
+            ),
+            _ => {
+                trace!("No index applies to {:?}, scanning all runs.", self);
+                let mut runs = database.runs().collect::<Vec<_>>();
+                runs.sort_by(|a, b| a.as_arc().cmp(b.as_arc()));
+                Box::new(runs.into_iter())
+            }
+        }
+    }
+}
+
+/// A query for games matching every filter that's been applied to it. Create
+/// one with [Database::query_games].
+#[derive(Debug, Clone)]
+pub struct GameQuery {
+    database: Arc<Database>,
+    tag_ids:  Vec<u64>,
+    years:    Option<(i32, i32)>,
+    romhack:  Option<bool>,
+}
+
+impl GameQuery {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            tag_ids: Vec::new(),
+            years: None,
+            romhack: None,
+        }
+    }
+
+    /// Only matches games with the given tag, in addition to any other tags
+    /// already required.
+    pub fn tag(mut self, tag: &GameTag) -> Self {
+        self.tag_ids.push(*tag.id());
+        self
+    }
+
+    /// Only matches games first released between the given years, inclusive.
+    /// Games with unknown release dates are excluded.
+    pub fn released_between(mut self, start_year: i32, end_year: i32) -> Self {
+        self.years = Some((start_year, end_year));
+        self
+    }
+
+    /// Only matches ROM hacks if true, or only other games if false.
+    pub fn romhack(mut self, romhack: bool) -> Self {
+        self.romhack = Some(romhack);
+        self
+    }
+
+    /// Returns whether a game matches every filter in this query.
+    pub fn matches(&self, game: &Game) -> bool {
+        self.tag_ids
+            .iter()
+            .all(|tag_id| game.tag_ids().contains(tag_id))
+            && self
+                .years
+                .map_or(true, |(start, end)| match game.release_date() {
+                    Some(date) => start <= date.year() && date.year() <= end,
+                    None => false,
+                })
+            && self
+                .romhack
+                .map_or(true, |romhack| *game.romhack() == romhack)
+    }
+
+    /// Iterates over the matching games, sorted by name and then ID.
+    pub fn iter(&self) -> impl Iterator<Item = Linked<Game>> + '_ {
+        self.candidates()
+            .into_iter()
+            .filter(move |game| self.matches(game))
+    }
+
+    /// Returns a Vec of the matching games, sorted by name and then ID.
+    pub fn to_vec(&self) -> Vec<Linked<Game>> {
+        self.iter().collect()
+    }
+
+    /// Returns a superset of the matching games, sorted by name and then ID.
+    fn candidates(&self) -> Vec<Linked<Game>> {
+        match self.tag_ids.first() {
+            Some(tag_id) => self.database.games_by_tag_id(*tag_id),
+            None => {
+                let mut games = self.database.games().collect::<Vec<_>>();
+                games.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+                games
+            }
+        }
+    }
+}
+
+impl Database {
+    /// Starts a query over all of the runs in this database.
+    pub fn query_runs(self: &Arc<Self>) -> RunQuery {
+        RunQuery::new(self.clone())
+    }
+
+    /// Starts a query over all of the games in this database.
+    pub fn query_games(self: &Arc<Self>) -> GameQuery {
+        GameQuery::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{self, category, level, user};
+
+    fn run(id: u64, level_id: Option<u64>, user_id: u64, day: u32, igt: bool) -> Run {
+        Run {
+            level_id,
+            date: Some(NaiveDate::from_ymd(2020, 1, day)),
+            times_ms: RunTimesMs {
+                igt:    if igt { Some(1000 + id) } else { None },
+                rta:    Some(2000 + id),
+                rta_nl: None,
+            },
+            ..fixtures::run(id, user_id, 2000 + id)
+        }
+    }
+
+    fn database() -> Arc<Database> {
+        fixtures::tables()
+            .runs(vec![
+                run(10, None, 5, 1, false),
+                run(11, None, 6, 2, true),
+                run(12, Some(3), 5, 3, true),
+                run(13, None, 5, 4, true),
+            ])
+            .users(vec![
+                user(5),
+                User {
+                    role: UserRole::Banned,
+                    ..user(6)
+                },
+            ])
+            .games(vec![fixtures::game(1)])
+            .categories(vec![category(2, 1)])
+            .levels(vec![level(3, 1)])
+            .database()
+    }
+
+    fn ids(query: RunQuery) -> Vec<u64> {
+        query.iter().map(|run| *run.id()).collect()
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let database = database();
+        let category = database.category_by_id(2).expect("category");
+        let user = database.user_by_id(5).expect("user");
+
+        assert_eq!(ids(database.query_runs()), vec![10, 11, 13, 12]);
+        assert_eq!(
+            ids(database.query_runs().category(&category).level(None)),
+            vec![10, 11, 13]
+        );
+        assert_eq!(ids(database.query_runs().user(&user)), vec![10, 13, 12]);
+        assert_eq!(
+            ids(database.query_runs().without_banned_users()),
+            vec![10, 13, 12]
+        );
+        assert_eq!(
+            ids(database
+                .query_runs()
+                .category(&category)
+                .level(None)
+                .user(&user)
+                .timing(TimingMethod::IGT)),
+            vec![13]
+        );
+        assert_eq!(
+            ids(database.query_runs().date_between(
+                NaiveDate::from_ymd(2020, 1, 2),
+                NaiveDate::from_ymd(2020, 1, 3)
+            )),
+            vec![11, 12]
+        );
+    }
+
+    fn game(
+        id: u64,
+        name: &str,
+        released: Option<i32>,
+        romhack: bool,
+        tag_ids: &[u64],
+    ) -> Game {
+        Game {
+            slug: name.to_lowercase(),
+            src_slug: name.to_lowercase(),
+            name: name.to_string(),
+            release_date: released.map(|year| NaiveDate::from_ymd(year, 6, 1)),
+            romhack,
+            tag_ids: tag_ids.to_vec(),
+            ..fixtures::game(id)
+        }
+    }
+
+    fn tag(id: u64, kind: GameTagKind, slug: &str) -> GameTag {
+        GameTag {
+            id,
+            kind,
+            slug: slug.to_string(),
+            name: slug.to_string(),
+        }
+    }
+
+    fn games_database() -> Arc<Database> {
+        fixtures::tables()
+            .games(vec![
+                game(3, "Gamma", None, false, &[]),
+                game(2, "Beta", Some(2005), true, &[40]),
+                game(1, "Alpha", Some(2001), false, &[40, 50]),
+            ])
+            .game_tags(vec![
+                tag(40, GameTagKind::Genre, "platformer"),
+                tag(50, GameTagKind::Developer, "nintendo"),
+            ])
+            .game_relations(vec![
+                GameRelation {
+                    game_id:      2,
+                    base_game_id: Some(1),
+                    series_ids:   vec![60],
+                },
+                GameRelation {
+                    game_id:      3,
+                    base_game_id: None,
+                    series_ids:   vec![60, 61],
+                },
+            ])
+            .database()
+    }
+
+    #[test]
+    fn test_game_query_filters() {
+        let database = games_database();
+        let platformer = database.game_tag_by_id(40).expect("tag");
+        let nintendo = database.game_tag_by_id(50).expect("tag");
+        let ids =
+            |query: GameQuery| query.iter().map(|game| *game.id()).collect::<Vec<_>>();
+
+        assert_eq!(ids(database.query_games()), vec![1, 2, 3]);
+        assert_eq!(ids(database.query_games().tag(&platformer)), vec![1, 2]);
+        assert_eq!(
+            ids(database.query_games().tag(&nintendo).tag(&platformer)),
+            vec![1]
+        );
+        assert_eq!(
+            ids(database.query_games().released_between(2000, 2003)),
+            vec![1]
+        );
+        assert_eq!(ids(database.query_games().romhack(true)), vec![2]);
+        assert_eq!(platformer.games().len(), 2);
+    }
+
+    #[test]
+    fn test_game_relations() {
+        let database = games_database();
+        let ids = |games: Vec<Linked<Game>>| {
+            games.iter().map(|game| *game.id()).collect::<Vec<_>>()
+        };
+        let alpha = database.game_by_id(1).expect("game");
+        let beta = database.game_by_id(2).expect("game");
+        let gamma = database.game_by_id(3).expect("game");
+
+        assert!(alpha.base_game().is_none());
+        assert_eq!(beta.base_game().map(|game| *game.id()), Some(1));
+        assert_eq!(ids(alpha.derived_games()), vec![2]);
+        assert_eq!(ids(alpha.romhacks()), vec![2]);
+        assert!(alpha.series().is_empty());
+        assert_eq!(ids(gamma.series()), vec![2, 3]);
+    }
+}
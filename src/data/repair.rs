@@ -0,0 +1,484 @@
+//! Repairs integrity errors in [Tables] by removing or renaming the records
+//! responsible for them, reporting every change made.
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    sync::Arc,
+};
+
+use itertools::Itertools;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use serde::Serialize;
+
+use crate::{
+    data::{
+        database::{Database, IntegrityError, Tables},
+        models::{AnyModel, AnyModelVec},
+    },
+    utils::base36,
+};
+
+/// What to do with records whose slugs conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SlugConflictPolicy {
+    /// Remove every record with the conflicting slug.
+    Drop,
+    /// Keep the oldest record with the conflicting slug, and remove the rest.
+    KeepOldest,
+    /// Keep the oldest record with the conflicting slug, and rename the rest by
+    /// appending their speedrun.com IDs to their slugs.
+    Rename,
+}
+
+/// Configures [Tables::repair].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepairPolicy {
+    pub slug_conflicts: SlugConflictPolicy,
+    /// The maximum number of validate-and-repair passes to make. Removing a
+    /// record can break foreign keys referring to it, which are only detected
+    /// by the next pass.
+    pub max_passes:     usize,
+}
+
+impl Default for RepairPolicy {
+    fn default() -> Self {
+        Self {
+            slug_conflicts: SlugConflictPolicy::KeepOldest,
+            max_passes:     16,
+        }
+    }
+}
+
+/// Why a record was changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RepairReason {
+    ForeignKeyMissing {
+        target_type:       &'static str,
+        target_id:         u64,
+        foreign_key_field: &'static str,
+    },
+    CheckFailed {
+        errors: String,
+    },
+    NonUniqueSlug {
+        slug: String,
+    },
+    MissingPrimaryTiming,
+}
+
+/// A change made to a record by [Tables::repair].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RepairChange {
+    Removed {
+        record: AnyModel,
+        reason: RepairReason,
+    },
+    Renamed {
+        record:   AnyModel,
+        new_slug: String,
+        reason:   RepairReason,
+    },
+}
+
+impl RepairChange {
+    /// The record as it was before this change.
+    pub fn record(&self) -> &AnyModel {
+        match self {
+            RepairChange::Removed { record, .. } => record,
+            RepairChange::Renamed { record, .. } => record,
+        }
+    }
+}
+
+/// Every change made by [Tables::repair].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RepairReport {
+    /// The number of repair passes made.
+    pub passes:  usize,
+    /// Whether the repaired tables passed validation.
+    pub valid:   bool,
+    pub changes: Vec<RepairChange>,
+}
+
+impl Display for RepairReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} changes in {} passes, {}:",
+            self.changes.len(),
+            self.passes,
+            if self.valid {
+                "now valid"
+            } else {
+                "still invalid"
+            }
+        )?;
+
+        let mut counts = BTreeMap::<(&str, &str), usize>::new();
+        for change in self.changes.iter() {
+            let action = match change {
+                RepairChange::Removed { .. } => "removed",
+                RepairChange::Renamed { .. } => "renamed",
+            };
+            *counts
+                .entry((action, change.record().table_name()))
+                .or_default() += 1;
+        }
+        for ((action, type_name), count) in counts {
+            writeln!(f, "{:8} {:6} {}", action, count, type_name)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Tables {
+    /// Repeatedly validates these tables and removes or renames the records
+    /// responsible for any integrity errors, until they're valid or we've
+    /// made [RepairPolicy::max_passes] passes.
+    pub fn repair(self, policy: &RepairPolicy) -> (Tables, RepairReport) {
+        let mut tables = self;
+        let mut report = RepairReport::default();
+
+        loop {
+            let errors = match Database::new(Arc::new(tables.clone())) {
+                Ok(_) => {
+                    report.valid = true;
+                    break
+                }
+                Err(errors) => errors,
+            };
+
+            if report.passes >= policy.max_passes {
+                error!(
+                    "Tables still invalid after {} repair passes.",
+                    report.passes
+                );
+                break
+            }
+            report.passes += 1;
+            debug!("Repair pass {}: {}", report.passes, errors);
+
+            let mut pass = RepairPass::default();
+            for error in errors.errors {
+                pass.plan_error(error, policy);
+            }
+
+            for change in pass.changes {
+                match &change {
+                    RepairChange::Removed { record, .. } => {
+                        tables.remove(record);
+                    }
+                    RepairChange::Renamed {
+                        record, new_slug, ..
+                    } => {
+                        tables.upsert(with_slug(record.clone(), new_slug.clone()));
+                    }
+                }
+                report.changes.push(change);
+            }
+        }
+
+        (tables, report)
+    }
+}
+
+/// The changes planned in a single pass, at most one per record.
+#[derive(Debug, Default)]
+struct RepairPass {
+    changed: HashSet<AnyModel>,
+    changes: Vec<RepairChange>,
+}
+
+impl RepairPass {
+    fn plan(&mut self, change: RepairChange) {
+        if self.changed.insert(change.record().clone()) {
+            self.changes.push(change);
+        }
+    }
+
+    fn remove(&mut self, record: AnyModel, reason: RepairReason) {
+        self.plan(RepairChange::Removed { record, reason });
+    }
+
+    fn plan_error(&mut self, error: IntegrityError, policy: &RepairPolicy) {
+        match error {
+            IntegrityError::IndexingError => {
+                debug!("indexing failed, expecting foreign key errors to explain why");
+            }
+            IntegrityError::ForeignKeyMissing {
+                target_type,
+                target_id,
+                foreign_key_field,
+                source,
+            } => self.remove(
+                source,
+                RepairReason::ForeignKeyMissing {
+                    target_type,
+                    target_id,
+                    foreign_key_field,
+                },
+            ),
+            IntegrityError::CheckFailed { errors, source } => self.remove(
+                source,
+                RepairReason::CheckFailed {
+                    errors: format!("{:?}", errors),
+                },
+            ),
+            IntegrityError::NonUniqueSlug { slug, sources } => {
+                let reason = RepairReason::NonUniqueSlug { slug };
+                let duplicates = oldest_first(sources);
+                let skip = match policy.slug_conflicts {
+                    SlugConflictPolicy::Drop => 0,
+                    SlugConflictPolicy::KeepOldest | SlugConflictPolicy::Rename => 1,
+                };
+                for record in duplicates.into_iter().skip(skip) {
+                    let new_slug = slug_of(&record)
+                        .map(|slug| format!("{}-{}", slug, src_id_of(&record)));
+                    match (policy.slug_conflicts, new_slug) {
+                        (SlugConflictPolicy::Rename, Some(new_slug)) =>
+                            self.plan(RepairChange::Renamed {
+                                record,
+                                new_slug,
+                                reason: reason.clone(),
+                            }),
+                        _ => self.remove(record, reason.clone()),
+                    }
+                }
+            }
+            IntegrityError::MissingPrimaryTiming(run) =>
+                self.remove(run.into(), RepairReason::MissingPrimaryTiming),
+        }
+    }
+}
+
+/// Sorts records with conflicting slugs so that the one we'd prefer to keep
+/// (generally the oldest) comes first.
+fn oldest_first(sources: AnyModelVec) -> Vec<AnyModel> {
+    match sources {
+        AnyModelVec::Runs(runs) => runs
+            .into_iter()
+            .sorted_by_key(|run| (run.created, run.id))
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Games(games) => games
+            .into_iter()
+            .sorted_by_key(|game| {
+                (
+                    game.created,
+                    game.slug.len(),
+                    game.name.len(),
+                    game.name.clone(),
+                    game.id,
+                )
+            })
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Users(users) => users
+            .into_iter()
+            .sorted_by_key(|user| {
+                (user.created, user.name.len(), user.name.clone(), user.id)
+            })
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Categories(categories) => categories
+            .into_iter()
+            .sorted_by_key(|category| {
+                (category.name.len(), category.name.clone(), category.id)
+            })
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Levels(levels) => levels
+            .into_iter()
+            .sorted_by_key(|level| (level.name.len(), level.name.clone(), level.id))
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Variables(variables) => variables
+            .into_iter()
+            .sorted_by_key(|variable| {
+                (variable.name.len(), variable.name.clone(), variable.id)
+            })
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Platforms(platforms) => platforms
+            .into_iter()
+            .sorted_by_key(|platform| {
+                (platform.name.len(), platform.name.clone(), platform.id)
+            })
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::Regions(regions) => regions
+            .into_iter()
+            .sorted_by_key(|region| (region.name.len(), region.name.clone(), region.id))
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::GameTags(tags) => tags
+            .into_iter()
+            .sorted_by_key(|tag| (tag.name.len(), tag.name.clone(), tag.id))
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::GameRelations(relations) => relations
+            .into_iter()
+            .sorted_by_key(|relation| relation.game_id)
+            .map(AnyModel::from)
+            .collect(),
+        AnyModelVec::UnverifiedRuns(runs) => runs
+            .into_iter()
+            .sorted_by_key(|unverified| (unverified.run.created, unverified.run.id))
+            .map(AnyModel::from)
+            .collect(),
+    }
+}
+
+fn src_id_of(record: &AnyModel) -> String {
+    match record {
+        AnyModel::Run(run) => run.src_id(),
+        AnyModel::User(user) => user.src_id(),
+        AnyModel::Game(game) => game.src_id(),
+        AnyModel::Category(category) => category.src_id(),
+        AnyModel::Level(level) => level.src_id(),
+        AnyModel::Variable(variable) => variable.src_id(),
+        AnyModel::Platform(platform) => platform.src_id(),
+        AnyModel::Region(region) => region.src_id(),
+        AnyModel::GameTag(tag) => tag.src_id(),
+        AnyModel::GameRelation(relation) => base36(relation.game_id),
+        AnyModel::UnverifiedRun(run) => run.src_id(),
+    }
+}
+
+fn slug_of(record: &AnyModel) -> Option<&str> {
+    match record {
+        AnyModel::Run(_) => None,
+        AnyModel::User(user) => Some(&user.slug),
+        AnyModel::Game(game) => Some(&game.slug),
+        AnyModel::Category(category) => Some(&category.slug),
+        AnyModel::Level(level) => Some(&level.slug),
+        AnyModel::Variable(variable) => Some(&variable.slug),
+        AnyModel::Platform(platform) => Some(&platform.slug),
+        AnyModel::Region(region) => Some(&region.slug),
+        AnyModel::GameTag(tag) => Some(&tag.slug),
+        AnyModel::GameRelation(_) => None,
+        AnyModel::UnverifiedRun(_) => None,
+    }
+}
+
+fn with_slug(record: AnyModel, slug: String) -> AnyModel {
+    match record {
+        AnyModel::Run(run) => AnyModel::Run(run),
+        AnyModel::User(mut user) => {
+            user.slug = slug;
+            AnyModel::User(user)
+        }
+        AnyModel::Game(mut game) => {
+            game.slug = slug;
+            AnyModel::Game(game)
+        }
+        AnyModel::Category(mut category) => {
+            category.slug = slug;
+            AnyModel::Category(category)
+        }
+        AnyModel::Level(mut level) => {
+            level.slug = slug;
+            AnyModel::Level(level)
+        }
+        AnyModel::Variable(mut variable) => {
+            variable.slug = slug;
+            AnyModel::Variable(variable)
+        }
+        AnyModel::Platform(mut platform) => {
+            platform.slug = slug;
+            AnyModel::Platform(platform)
+        }
+        AnyModel::Region(mut region) => {
+            region.slug = slug;
+            AnyModel::Region(region)
+        }
+        AnyModel::GameTag(mut tag) => {
+            tag.slug = slug;
+            AnyModel::GameTag(tag)
+        }
+        AnyModel::GameRelation(relation) => AnyModel::GameRelation(relation),
+        AnyModel::UnverifiedRun(run) => AnyModel::UnverifiedRun(run),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{
+        fixtures::{self, category},
+        types::*,
+    };
+
+    fn game(id: u64, slug: &str) -> Game {
+        Game {
+            slug: slug.to_string(),
+            src_slug: slug.to_string(),
+            name: slug.to_string(),
+            ..fixtures::game(id)
+        }
+    }
+
+    fn tables() -> Tables {
+        fixtures::tables()
+            .games(vec![game(1, "dupe"), game(2, "dupe"), game(3, "other")])
+            .categories(vec![category(4, 2)])
+            .game_relations(vec![GameRelation {
+                game_id:      3,
+                base_game_id: Some(2),
+                series_ids:   vec![],
+            }])
+            .build()
+    }
+
+    #[test]
+    fn test_keep_oldest_cascades() {
+        let (tables, report) = tables().repair(&RepairPolicy::default());
+
+        assert!(report.valid);
+        assert_eq!(report.passes, 2);
+        assert_eq!(
+            tables.games().keys().cloned().collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert!(tables.categories().is_empty());
+        assert!(tables.game_relations().is_empty());
+        assert_eq!(report.changes.len(), 3);
+        match &report.changes[1] {
+            RepairChange::Removed {
+                reason: RepairReason::ForeignKeyMissing { target_id, .. },
+                ..
+            } => assert_eq!(*target_id, 2),
+            other => panic!("unexpected change: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rename_keeps_records() {
+        let (tables, report) = tables().repair(&RepairPolicy {
+            slug_conflicts: SlugConflictPolicy::Rename,
+            ..RepairPolicy::default()
+        });
+
+        assert!(report.valid);
+        assert_eq!(report.passes, 1);
+        assert_eq!(tables.games().len(), 3);
+        assert_eq!(
+            tables.games()[&2].slug,
+            format!("dupe-{}", game(2, "").src_id())
+        );
+        assert_eq!(tables.categories().len(), 1);
+        assert_eq!(tables.game_relations().len(), 1);
+    }
+
+    #[test]
+    fn test_drop_removes_all() {
+        let (tables, report) = tables().repair(&RepairPolicy {
+            slug_conflicts: SlugConflictPolicy::Drop,
+            ..RepairPolicy::default()
+        });
+
+        assert!(report.valid);
+        assert_eq!(tables.games().keys().cloned().collect::<Vec<_>>(), vec![3]);
+    }
+}
@@ -0,0 +1,405 @@
+//! Ranked fuzzy search over the names of games, categories, levels, and users.
+//!
+//! Names and queries are folded with [slugify], so matching ignores case,
+//! accents, and punctuation, and then split into tokens. Every query token
+//! must match a token of the result exactly, as a prefix, or within a small
+//! edit distance. Category and level results can also match tokens from
+//! their game's name, but at least one token must match their own name.
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::{
+    data::{
+        database::{Database, Linked, Tables},
+        types::*,
+    },
+    utils::slugify,
+};
+
+const EXACT_TOKEN_SCORE: u32 = 8;
+const PREFIX_TOKEN_SCORE: u32 = 4;
+const FUZZY_TOKEN_SCORE: u32 = 2;
+const EXACT_NAME_BONUS: u32 = 16;
+const PREFIX_NAME_BONUS: u32 = 8;
+
+/// Query tokens shorter than this are only matched exactly, because they'd
+/// otherwise be a prefix of too many tokens to be useful.
+const MIN_PREFIX_LEN: usize = 2;
+
+/// The types of records that can be searched, in the order that we rank
+/// otherwise-equal results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum SearchType {
+    Game,
+    Category,
+    Level,
+    User,
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchResult {
+    Game(Linked<Game>),
+    Category(Linked<Category>),
+    Level(Linked<Level>),
+    User(Linked<User>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// Relevance of this result. Only meaningful relative to other results for
+    /// the same query.
+    pub score:  u32,
+    pub result: SearchResult,
+}
+
+#[derive(Debug)]
+struct Entry {
+    search_type: SearchType,
+    id:          u64,
+    slug:        String,
+}
+
+/// A token index over the names of records in [Tables].
+#[derive(Default)]
+pub struct SearchIndex {
+    entries:  Vec<Entry>,
+    /// Maps each token to the indices of the entries it occurs in, and whether
+    /// it occurs in the entry's own name (true) or only its game's (false).
+    postings: BTreeMap<String, Vec<(usize, bool)>>,
+}
+
+impl std::fmt::Debug for SearchIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SearchIndex {{ .. }}")
+    }
+}
+
+impl SearchIndex {
+    /// Builds a search index of the names in the given tables.
+    pub fn new(tables: &Tables) -> Self {
+        let mut index = Self::default();
+
+        for game in tables.games().values() {
+            index.insert(SearchType::Game, game.id, &game.name, None);
+        }
+
+        for category in tables.categories().values() {
+            let game_name = tables
+                .games()
+                .get(&category.game_id)
+                .map(|game| game.name.as_str());
+            index.insert(SearchType::Category, category.id, &category.name, game_name);
+        }
+
+        for level in tables.levels().values() {
+            let game_name = tables
+                .games()
+                .get(&level.game_id)
+                .map(|game| game.name.as_str());
+            index.insert(SearchType::Level, level.id, &level.name, game_name);
+        }
+
+        for user in tables.users().values() {
+            index.insert(SearchType::User, user.id, &user.name, None);
+        }
+
+        for postings in index.postings.values_mut() {
+            postings.sort();
+            postings.dedup();
+        }
+
+        index
+    }
+
+    fn insert(
+        &mut self,
+        search_type: SearchType,
+        id: u64,
+        name: &str,
+        context: Option<&str>,
+    ) {
+        let entry = self.entries.len();
+        let slug = slugify(name);
+
+        for token in tokens(&slug) {
+            self.postings
+                .entry(token.to_string())
+                .or_insert_with(Vec::new)
+                .push((entry, true));
+        }
+
+        if let Some(context) = context {
+            for token in tokens(&slugify(context)) {
+                self.postings
+                    .entry(token.to_string())
+                    .or_insert_with(Vec::new)
+                    .push((entry, false));
+            }
+        }
+
+        self.entries.push(Entry {
+            search_type,
+            id,
+            slug,
+        });
+    }
+
+    /// Returns up to `limit` results matching `query`, best first, including
+    /// only the given `types` of results (or all types if it's empty).
+    pub fn search(
+        &self,
+        database: &Arc<Database>,
+        query: &str,
+        types: &[SearchType],
+        limit: usize,
+    ) -> Vec<SearchHit> {
+        let query_slug = slugify(query);
+
+        // maps each entry to its total score and whether it matched its own name
+        let mut matches: Option<HashMap<usize, (u32, bool)>> = None;
+        for query_token in tokens(&query_slug) {
+            let token_matches = self.token_matches(query_token);
+            matches = Some(match matches {
+                None => token_matches,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(entry, (score, own_name))| {
+                        token_matches
+                            .get(&entry)
+                            .map(|(token_score, token_own_name)| {
+                                (entry, (score + token_score, own_name || *token_own_name))
+                            })
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<(u32, usize)> = matches
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(entry, (_score, own_name))| {
+                *own_name
+                    && (types.is_empty()
+                        || types.contains(&self.entries[*entry].search_type))
+            })
+            .map(|(entry, (score, _own_name))| {
+                let slug = &self.entries[entry].slug;
+                let bonus = if *slug == query_slug {
+                    EXACT_NAME_BONUS
+                } else if slug.starts_with(&query_slug) {
+                    PREFIX_NAME_BONUS
+                } else {
+                    0
+                };
+                (score + bonus, entry)
+            })
+            .collect();
+
+        hits.sort_by(|(a_score, a), (b_score, b)| {
+            let a = &self.entries[*a];
+            let b = &self.entries[*b];
+            b_score
+                .cmp(a_score)
+                .then(a.search_type.cmp(&b.search_type))
+                .then(a.slug.len().cmp(&b.slug.len()))
+                .then(a.slug.cmp(&b.slug))
+                .then(a.id.cmp(&b.id))
+        });
+
+        hits.into_iter()
+            .take(limit)
+            .filter_map(|(score, entry)| {
+                let entry = &self.entries[entry];
+                let result = match entry.search_type {
+                    SearchType::Game =>
+                        database.game_by_id(entry.id).map(SearchResult::Game),
+                    SearchType::Category => database
+                        .category_by_id(entry.id)
+                        .map(SearchResult::Category),
+                    SearchType::Level =>
+                        database.level_by_id(entry.id).map(SearchResult::Level),
+                    SearchType::User =>
+                        database.user_by_id(entry.id).map(SearchResult::User),
+                }?;
+                Some(SearchHit { score, result })
+            })
+            .collect()
+    }
+
+    /// Returns the best score for each entry matching a single query token,
+    /// and whether that token matched the entry's own name.
+    fn token_matches(&self, query_token: &str) -> HashMap<usize, (u32, bool)> {
+        let mut matches = HashMap::<usize, (u32, bool)>::new();
+        let mut add = |postings: &Vec<(usize, bool)>, score: u32| {
+            for (entry, own_name) in postings {
+                let score = if *own_name { score } else { score / 2 };
+                let best = matches.entry(*entry).or_insert((0, false));
+                best.0 = best.0.max(score);
+                best.1 |= *own_name;
+            }
+        };
+
+        if let Some(postings) = self.postings.get(query_token) {
+            add(postings, EXACT_TOKEN_SCORE);
+        }
+
+        if query_token.len() >= MIN_PREFIX_LEN {
+            for (_token, postings) in self
+                .postings
+                .range::<str, _>((Bound::Excluded(query_token), Bound::Unbounded))
+                .take_while(|(token, _)| token.starts_with(query_token))
+            {
+                add(postings, PREFIX_TOKEN_SCORE);
+            }
+        }
+
+        let max_distance = match query_token.len() {
+            0..=3 => 0,
+            4..=7 => 1,
+            _ => 2,
+        };
+        if max_distance > 0 {
+            // Only consider tokens with the same first character, so we don't need
+            // to compare against the entire vocabulary.
+            let first = &query_token[..1];
+            for (token, postings) in self
+                .postings
+                .range::<str, _>((Bound::Included(first), Bound::Unbounded))
+                .take_while(|(token, _)| token.starts_with(first))
+            {
+                if token.starts_with(query_token)
+                    || token.len() + max_distance < query_token.len()
+                    || token.len() > query_token.len() + max_distance
+                {
+                    continue
+                }
+                if edit_distance(token, query_token) <= max_distance {
+                    add(postings, FUZZY_TOKEN_SCORE);
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+/// Splits a slug into its tokens.
+fn tokens(slug: &str) -> impl Iterator<Item = &str> {
+    slug.split('-').filter(|token| !token.is_empty())
+}
+
+/// The optimal string alignment distance between two ASCII strings: the
+/// number of single-character insertions, deletions, substitutions, or
+/// adjacent transpositions needed to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+
+    let mut previous_previous = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + substitution_cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(previous_previous[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut previous_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{self, category, tables, user};
+
+    fn game(id: u64, name: &str) -> Game {
+        Game {
+            slug: slugify(name),
+            src_slug: slugify(name),
+            name: name.to_string(),
+            ..fixtures::game(id)
+        }
+    }
+
+    fn database() -> Arc<Database> {
+        tables()
+            .users(vec![User {
+                slug: "celestefan".to_string(),
+                name: "CelesteFan".to_string(),
+                ..user(10)
+            }])
+            .games(vec![
+                game(1, "Pokémon Blue"),
+                game(2, "Pokémon Red"),
+                game(3, "Celeste"),
+            ])
+            .categories(vec![category(4, 3)])
+            .database()
+    }
+
+    fn result_ids(hits: &[SearchHit]) -> Vec<(SearchType, u64)> {
+        hits.iter()
+            .map(|hit| match &hit.result {
+                SearchResult::Game(game) => (SearchType::Game, game.id),
+                SearchResult::Category(category) => (SearchType::Category, category.id),
+                SearchResult::Level(level) => (SearchType::Level, level.id),
+                SearchResult::User(user) => (SearchType::User, user.id),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let database = database();
+
+        let hits = database.search("pokémon bl", &[], 16);
+        assert_eq!(result_ids(&hits), vec![(SearchType::Game, 1)]);
+
+        let hits = database.search("POKEMON", &[], 16);
+        assert_eq!(
+            result_ids(&hits),
+            vec![(SearchType::Game, 2), (SearchType::Game, 1)]
+        );
+
+        let hits = database.search("celeset", &[], 16);
+        assert_eq!(result_ids(&hits), vec![(SearchType::Game, 3)]);
+
+        let hits = database.search("celeste", &[], 16);
+        assert_eq!(
+            result_ids(&hits),
+            vec![(SearchType::Game, 3), (SearchType::User, 10)]
+        );
+
+        let hits = database.search("celeste any", &[], 16);
+        assert_eq!(result_ids(&hits), vec![(SearchType::Category, 4)]);
+
+        let hits = database.search("celeste", &[SearchType::User], 16);
+        assert_eq!(result_ids(&hits), vec![(SearchType::User, 10)]);
+
+        assert!(database.search("", &[], 16).is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("celeste", "celeste"), 0);
+        assert_eq!(edit_distance("celeste", "celeset"), 1);
+        assert_eq!(edit_distance("celeste", "celest"), 1);
+        assert_eq!(edit_distance("celeste", "xeleste"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
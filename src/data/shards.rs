@@ -0,0 +1,527 @@
+//! Splits [Tables] into per-game shards that can be loaded on demand, so that
+//! we can serve far more runs than fit in memory at once.
+//!
+//! A shard directory contains a `manifest.json` naming the current generation of
+//! shards, which is a subdirectory containing:
+//!
+//! - `index.bin`: a snapshot of every game, category, level, variable, platform, region,
+//!   game tag, game relation, and user, but no runs. Relations between games are only in
+//!   the index, since they cross shards.
+//! - `games/{game_id}.bin`: a snapshot of one game's verified and unverified runs, along
+//!   with the game, its categories, levels, variables, and tags, the users who ran,
+//!   examined, or moderate it, and every platform and region, since there are few of them.
+//! - `user_games.bin`: the IDs of the games each user has verified runs in, serialized with
+//!   bincode, so that a user's runs can be found without loading every shard.
+//! - `run_games.bin`: the ID of the game of each verified run, serialized with bincode, so
+//!   that a run can be found by its ID alone.
+//!
+//! Each write creates a new generation and then atomically replaces the manifest, so
+//! readers never see a partially-written set of shards. The previous generation is kept
+//! until the next write, since a running server may still be loading shards from it.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fs::File,
+    io::{prelude::*, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use err_derive::Error;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::data::{
+    database::{Database, IntegrityErrors, Linked, Tables},
+    snapshot::SnapshotError,
+    types::*,
+};
+
+#[derive(Debug, Error)]
+pub enum ShardError {
+    #[error(display = "shard I/O failed: {}", _0)]
+    Io(std::io::Error),
+    #[error(display = "shard manifest JSON invalid: {}", _0)]
+    Manifest(serde_json::Error),
+    #[error(display = "failed to read shard: {}", _0)]
+    Snapshot(SnapshotError),
+    #[error(display = "shard is invalid: {}", _0)]
+    Integrity(IntegrityErrors),
+}
+
+impl From<std::io::Error> for ShardError {
+    fn from(error: std::io::Error) -> Self {
+        ShardError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ShardError {
+    fn from(error: serde_json::Error) -> Self {
+        ShardError::Manifest(error)
+    }
+}
+
+impl From<bincode::Error> for ShardError {
+    fn from(error: bincode::Error) -> Self {
+        ShardError::Snapshot(error.into())
+    }
+}
+
+impl From<SnapshotError> for ShardError {
+    fn from(error: SnapshotError) -> Self {
+        ShardError::Snapshot(error)
+    }
+}
+
+impl From<IntegrityErrors> for ShardError {
+    fn from(errors: IntegrityErrors) -> Self {
+        ShardError::Integrity(errors)
+    }
+}
+
+/// Names the current generation of shards in a shard directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShardManifest {
+    /// The name of the generation's subdirectory.
+    generation: String,
+}
+
+impl ShardManifest {
+    fn path(dir: &Path) -> PathBuf {
+        dir.join("manifest.json")
+    }
+
+    /// Reads the manifest from a shard directory, or None if there isn't one.
+    fn read(dir: &Path) -> Result<Option<Self>, ShardError> {
+        match File::open(Self::path(dir)) {
+            Ok(file) => Ok(Some(serde_json::from_reader(BufReader::new(file))?)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Writes this manifest to a shard directory, atomically replacing any
+    /// existing one.
+    fn write(&self, dir: &Path) -> Result<(), ShardError> {
+        let mut file = NamedTempFile::new_in(dir)?;
+        {
+            let mut buffer = BufWriter::new(&mut file);
+            serde_json::to_writer_pretty(&mut buffer, self)?;
+            buffer.write_all(b"\n")?;
+        }
+        file.persist(Self::path(dir)).map_err(|error| error.error)?;
+        Ok(())
+    }
+}
+
+const GENERATION_PREFIX: &str = "generation-";
+
+fn index_path(dir: &Path) -> PathBuf {
+    dir.join("index.bin")
+}
+
+fn user_games_path(dir: &Path) -> PathBuf {
+    dir.join("user_games.bin")
+}
+
+fn run_games_path(dir: &Path) -> PathBuf {
+    dir.join("run_games.bin")
+}
+
+fn game_path(dir: &Path, game_id: u64) -> PathBuf {
+    dir.join("games").join(format!("{}.bin", game_id))
+}
+
+impl Tables {
+    /// Writes these tables to a new generation in `dir` as a global index and a
+    /// shard per game, then makes it the current generation, removing any
+    /// older than the one it replaces.
+    pub fn write_shards(&self, dir: impl AsRef<Path>) -> Result<(), ShardError> {
+        let root = dir.as_ref();
+        std::fs::create_dir_all(root)?;
+        let previous = ShardManifest::read(root)?;
+
+        // removed if we fail before it's complete
+        let generation = tempfile::Builder::new()
+            .prefix(GENERATION_PREFIX)
+            .tempdir_in(root)?;
+        let dir = generation.path();
+        std::fs::create_dir_all(dir.join("games"))?;
+
+        let mut runs_by_game_id = group_by_game_id(self.runs(), |run| run.game_id);
+        let mut categories_by_game_id =
+            group_by_game_id(self.categories(), |category| category.game_id);
+        let mut levels_by_game_id = group_by_game_id(self.levels(), |level| level.game_id);
+        let mut variables_by_game_id =
+            group_by_game_id(self.variables(), |variable| variable.game_id);
+        let mut unverified_runs_by_game_id =
+            group_by_game_id(self.unverified_runs(), |run| run.run.game_id);
+        let mut game_ids_by_user_id = BTreeMap::<u64, Vec<u64>>::new();
+        let game_ids_by_run_id: BTreeMap<u64, u64> = self
+            .runs()
+            .values()
+            .map(|run| (run.id, run.game_id))
+            .collect();
+
+        for game in self.games().values() {
+            let runs = runs_by_game_id.remove(&game.id).unwrap_or_default();
+            for run in runs.iter() {
+                for player in run.players.iter() {
+                    if let RunPlayer::UserId(user_id) = player {
+                        let game_ids = game_ids_by_user_id.entry(*user_id).or_default();
+                        if game_ids.last() != Some(&game.id) {
+                            game_ids.push(game.id);
+                        }
+                    }
+                }
+            }
+            let unverified_runs = unverified_runs_by_game_id
+                .remove(&game.id)
+                .unwrap_or_default();
+            let all_runs = runs
+                .iter()
+                .chain(unverified_runs.iter().map(|unverified| &unverified.run));
+            let mut user_ids = BTreeSet::<u64>::new();
+            for run in all_runs {
+                for player in run.players.iter() {
+                    if let RunPlayer::UserId(user_id) = player {
+                        user_ids.insert(*user_id);
+                    }
+                }
+                user_ids.extend(run.examiner_id);
+            }
+            user_ids.extend(game.moderators.iter().map(|moderator| moderator.user_id));
+            let users = user_ids
+                .iter()
+                .filter_map(|id| self.users().get(id))
+                .map(|user| User::clone(user))
+                .collect();
+            let tags = game
+                .tag_ids
+                .iter()
+                .filter_map(|id| self.game_tags().get(id))
+                .map(|tag| GameTag::clone(tag))
+                .collect();
+
+            let shard = Tables::new(
+                runs,
+                users,
+                vec![Game::clone(game)],
+                categories_by_game_id.remove(&game.id).unwrap_or_default(),
+                levels_by_game_id.remove(&game.id).unwrap_or_default(),
+                variables_by_game_id.remove(&game.id).unwrap_or_default(),
+                clone_all(self.platforms()),
+                clone_all(self.regions()),
+                tags,
+                vec![],
+                unverified_runs,
+            );
+            shard.write_snapshot(game_path(dir, game.id))?;
+        }
+
+        let index = Tables::new(
+            vec![],
+            clone_all(self.users()),
+            clone_all(self.games()),
+            clone_all(self.categories()),
+            clone_all(self.levels()),
+            clone_all(self.variables()),
+            clone_all(self.platforms()),
+            clone_all(self.regions()),
+            clone_all(self.game_tags()),
+            clone_all(self.game_relations()),
+            vec![],
+        );
+        index.write_snapshot(index_path(dir))?;
+
+        let mut user_games = BufWriter::new(File::create(user_games_path(dir))?);
+        bincode::serialize_into(&mut user_games, &game_ids_by_user_id)?;
+        user_games.flush()?;
+        let mut run_games = BufWriter::new(File::create(run_games_path(dir))?);
+        bincode::serialize_into(&mut run_games, &game_ids_by_run_id)?;
+        run_games.flush()?;
+
+        let generation = generation.into_path();
+        let manifest = ShardManifest {
+            generation: generation
+                .file_name()
+                .expect("generation has a name")
+                .to_string_lossy()
+                .into_owned(),
+        };
+        manifest.write(root)?;
+
+        for entry in std::fs::read_dir(root)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let keep = name == manifest.generation
+                || previous
+                    .as_ref()
+                    .map_or(false, |previous| name == previous.generation);
+            if name.starts_with(GENERATION_PREFIX) && !keep {
+                debug!("Removing old shard generation {:?}...", name);
+                std::fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn clone_all<T: Clone>(table: &BTreeMap<u64, Arc<T>>) -> Vec<T> {
+    table.values().map(|record| T::clone(record)).collect()
+}
+
+fn group_by_game_id<T: Clone>(
+    table: &BTreeMap<u64, Arc<T>>,
+    game_id: impl Fn(&T) -> u64,
+) -> HashMap<u64, Vec<T>> {
+    let mut groups = HashMap::<u64, Vec<T>>::new();
+    for record in table.values() {
+        groups
+            .entry(game_id(record))
+            .or_insert_with(Vec::new)
+            .push(T::clone(record));
+    }
+    groups
+}
+
+/// A database split into per-game shards, with every game, category, level,
+/// variable, platform, region, and user always loaded in an index [Database], but only the
+/// most recently used games' runs loaded in their own shard [Database]s.
+pub struct ShardedDatabase {
+    dir:                 PathBuf,
+    index:               Arc<Database>,
+    game_ids_by_user_id: BTreeMap<u64, Vec<u64>>,
+    game_ids_by_run_id:  BTreeMap<u64, u64>,
+    capacity:            usize,
+    /// Resident shards, most recently used first.
+    resident:            Mutex<VecDeque<(u64, Arc<Database>)>>,
+}
+
+impl std::fmt::Debug for ShardedDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ShardedDatabase {{ dir: {:?}, .. }}", self.dir)
+    }
+}
+
+impl ShardedDatabase {
+    /// Opens the current generation of shards written by [Tables::write_shards]
+    /// to `dir`, keeping up to `capacity` games' shards loaded at once.
+    pub fn open(dir: impl AsRef<Path>, capacity: usize) -> Result<Self, ShardError> {
+        let root = dir.as_ref();
+        let manifest = ShardManifest::read(root)?.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no shard manifest in {:?}", root),
+            )
+        })?;
+        let dir = root.join(manifest.generation);
+        let index = Database::new(Arc::new(Tables::load_snapshot(index_path(&dir))?))?;
+        let user_games = BufReader::new(File::open(user_games_path(&dir))?);
+        let game_ids_by_user_id = bincode::deserialize_from(user_games)?;
+        let run_games = BufReader::new(File::open(run_games_path(&dir))?);
+        let game_ids_by_run_id = bincode::deserialize_from(run_games)?;
+        Ok(Self {
+            dir,
+            index,
+            game_ids_by_user_id,
+            game_ids_by_run_id,
+            capacity: capacity.max(1),
+            resident: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// The index of every game, category, level, variable, platform, region, and
+    /// user, without any runs.
+    pub fn index(&self) -> &Arc<Database> {
+        &self.index
+    }
+
+    /// Returns the shard with the given game's runs, loading it if it isn't
+    /// already resident, or None if there's no such game.
+    ///
+    /// The resident shards aren't locked while a shard loads, so other games'
+    /// resident shards can still be used, and if the same shard is loaded
+    /// concurrently, the first to finish is kept.
+    pub fn game_database(&self, game_id: u64) -> Result<Option<Arc<Database>>, ShardError> {
+        if self.index.game_by_id(game_id).is_none() {
+            return Ok(None)
+        }
+
+        if let Some(database) = touch_resident(
+            &mut self.resident.lock().expect("shard lock poisoned"),
+            game_id,
+        ) {
+            return Ok(Some(database))
+        }
+
+        debug!("Loading shard for game {}...", game_id);
+        let tables = Tables::load_snapshot(game_path(&self.dir, game_id))?;
+        let database = Database::new(Arc::new(tables))?;
+
+        let mut resident = self.resident.lock().expect("shard lock poisoned");
+        if let Some(database) = touch_resident(&mut resident, game_id) {
+            return Ok(Some(database))
+        }
+
+        resident.push_front((game_id, database.clone()));
+        while resident.len() > self.capacity {
+            if let Some((evicted, _)) = resident.pop_back() {
+                debug!("Evicting shard for game {}.", evicted);
+            }
+        }
+
+        Ok(Some(database))
+    }
+
+    /// The IDs of the games the given user has verified runs in, ascending.
+    pub fn game_ids_by_user_id(&self, user_id: u64) -> &[u64] {
+        self.game_ids_by_user_id
+            .get(&user_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// The ID of the given verified run's game, if it exists.
+    pub fn game_id_by_run_id(&self, run_id: u64) -> Option<u64> {
+        self.game_ids_by_run_id.get(&run_id).copied()
+    }
+
+    /// Finds a run by ID, linked to its game's shard.
+    pub fn run_by_id(&self, run_id: u64) -> Result<Option<Linked<Run>>, ShardError> {
+        let game_id = match self.game_id_by_run_id(run_id) {
+            Some(game_id) => game_id,
+            None => return Ok(None),
+        };
+        Ok(self
+            .game_database(game_id)?
+            .and_then(|database| database.run_by_id(run_id)))
+    }
+
+    /// Finds a game by slug, linked to its shard.
+    pub fn game_by_slug(&self, slug: &str) -> Result<Option<Linked<Game>>, ShardError> {
+        let game_id = match self.index.game_by_slug(slug) {
+            Some(game) => game.id,
+            None => return Ok(None),
+        };
+        Ok(self
+            .game_database(game_id)?
+            .and_then(|database| database.game_by_id(game_id)))
+    }
+
+    /// The IDs of the games whose shards are currently loaded, most recently
+    /// used first.
+    pub fn resident_game_ids(&self) -> Vec<u64> {
+        self.resident
+            .lock()
+            .expect("shard lock poisoned")
+            .iter()
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Returns the given game's shard if it's resident, marking it as the most
+/// recently used.
+fn touch_resident(
+    resident: &mut VecDeque<(u64, Arc<Database>)>,
+    game_id: u64,
+) -> Option<Arc<Database>> {
+    let position = resident.iter().position(|(id, _)| *id == game_id)?;
+    let shard = resident.remove(position).expect("position is in range");
+    resident.push_front(shard.clone());
+    Some(shard.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{self, category, user};
+
+    fn game(id: u64) -> Game {
+        Game {
+            tag_ids: vec![id * 10 + 1],
+            ..fixtures::game(id)
+        }
+    }
+
+    fn run(id: u64, game_id: u64) -> Run {
+        Run {
+            game_id,
+            category_id: game_id * 10,
+            ..fixtures::run(id, 7, 1000)
+        }
+    }
+
+    #[test]
+    fn test_shards_load_lazily_with_lru() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let tables = fixtures::tables()
+            .runs(vec![run(100, 1), run(101, 1), run(200, 2), run(300, 3)])
+            .users(vec![user(7)])
+            .games(vec![game(1), game(2), game(3)])
+            .categories(
+                (1..=3)
+                    .map(|game_id| category(game_id * 10, game_id))
+                    .collect(),
+            )
+            .game_tags(
+                (1..=3)
+                    .map(|game_id| GameTag {
+                        id:   game_id * 10 + 1,
+                        kind: GameTagKind::Genre,
+                        slug: format!("genre-{}", game_id),
+                        name: format!("Genre {}", game_id),
+                    })
+                    .collect(),
+            )
+            .build();
+        tables.write_shards(dir.path()).expect("shards written");
+
+        let shards = ShardedDatabase::open(dir.path(), 2).expect("shards opened");
+        assert_eq!(shards.index().runs().count(), 0);
+        assert!(shards.index().user_by_slug("user-7").is_some());
+        assert!(shards.resident_game_ids().is_empty());
+        assert_eq!(shards.game_ids_by_user_id(7), &[1, 2, 3]);
+        assert!(shards.game_ids_by_user_id(8).is_empty());
+        assert_eq!(shards.game_id_by_run_id(200), Some(2));
+        assert_eq!(shards.game_id_by_run_id(201), None);
+
+        let game_1 = shards
+            .game_by_slug("game-1")
+            .expect("shard loaded")
+            .expect("game exists");
+        assert_eq!(game_1.runs().len(), 2);
+        assert_eq!(game_1.database().game_tags().count(), 1);
+        assert_eq!(shards.index().game_tags().count(), 3);
+        shards.game_database(2).expect("shard loaded");
+        shards.game_database(1).expect("shard loaded");
+        shards.game_database(3).expect("shard loaded");
+        assert_eq!(shards.resident_game_ids(), vec![3, 1]);
+        let run = shards
+            .run_by_id(101)
+            .expect("shard loaded")
+            .expect("run exists");
+        assert_eq!(run.game().id, 1);
+        assert_eq!(shards.resident_game_ids(), vec![1, 3]);
+
+        assert!(shards.game_database(4).expect("no error").is_none());
+
+        // rewriting keeps the generation that's being read from, but no older ones
+        let generations = || {
+            std::fs::read_dir(dir.path())
+                .expect("shard dir read")
+                .filter(|entry| entry.as_ref().expect("entry read").path().is_dir())
+                .count()
+        };
+        tables.write_shards(dir.path()).expect("shards rewritten");
+        assert_eq!(generations(), 2);
+        shards.game_database(2).expect("old shard still loads");
+        tables.write_shards(dir.path()).expect("shards rewritten");
+        assert_eq!(generations(), 2);
+
+        ShardedDatabase::open(dir.path(), 2).expect("shards reopened");
+    }
+}
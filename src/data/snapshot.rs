@@ -0,0 +1,241 @@
+//! A compact binary snapshot of [Tables], which is much faster to load than
+//! re-parsing the normalized JSONL tables.
+//!
+//! The file format is:
+//!
+//! - 8 bytes: [MAGIC]
+//! - 4 bytes: [SNAPSHOT_VERSION], little-endian
+//! - 32 bytes: SHA-256 digest of the payload
+//! - the payload: the [Tables] serialized with bincode
+use std::{
+    fs::File,
+    io::{prelude::*, BufReader, BufWriter},
+    path::Path,
+    time::SystemTime,
+};
+
+use err_derive::Error;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+
+use crate::data::database::Tables;
+
+/// Identifies a file as a speedruns table snapshot.
+pub const MAGIC: &[u8; 8] = b"SPDRUNS\0";
+
+/// The current snapshot format version. This must be incremented whenever the
+/// serialized layout of [Tables] changes, because bincode isn't self-describing
+/// and would otherwise silently misread old snapshots.
+pub const SNAPSHOT_VERSION: u32 = 8;
+
+const HEADER_LEN: usize = 8 + 4 + 32;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error(display = "snapshot I/O failed: {}", _0)]
+    Io(std::io::Error),
+    #[error(display = "snapshot (de)serialization failed: {}", _0)]
+    Encoding(bincode::Error),
+    #[error(display = "file is not a table snapshot")]
+    NotASnapshot,
+    #[error(
+        display = "snapshot version {} is not supported (expected {})",
+        found,
+        expected
+    )]
+    UnsupportedVersion { found: u32, expected: u32 },
+    #[error(display = "snapshot checksum doesn't match, file is corrupt or truncated")]
+    ChecksumMismatch,
+    #[error(display = "snapshot is older than the tables it was written from")]
+    Stale,
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(error: bincode::Error) -> Self {
+        SnapshotError::Encoding(error)
+    }
+}
+
+impl Tables {
+    /// Writes a versioned and checksummed binary snapshot of these tables to
+    /// `path`, atomically replacing any existing file.
+    pub fn write_snapshot(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let payload = bincode::serialize(self)?;
+        let digest = Sha256::digest(&payload);
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut file = NamedTempFile::new_in(dir)?;
+        {
+            let mut buffer = BufWriter::new(&mut file);
+            buffer.write_all(MAGIC)?;
+            buffer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+            buffer.write_all(digest.as_slice())?;
+            buffer.write_all(&payload)?;
+            buffer.flush()?;
+        }
+        file.persist(path).map_err(|error| error.error)?;
+
+        Ok(())
+    }
+
+    /// Loads tables from a snapshot written by [Tables::write_snapshot],
+    /// verifying its version and checksum.
+    pub fn load_snapshot(path: impl AsRef<Path>) -> Result<Tables, SnapshotError> {
+        let file = File::open(path)?;
+        let mut bytes = Vec::new();
+        BufReader::new(file).read_to_end(&mut bytes)?;
+        Tables::from_snapshot_bytes(&bytes)
+    }
+
+    /// Loads tables from a snapshot like [Tables::load_snapshot], but only if
+    /// it was written after every JSONL table and manifest in `source_dir`, so
+    /// that we don't serve stale data after the tables are re-normalized.
+    pub fn load_fresh_snapshot(
+        path: impl AsRef<Path>,
+        source_dir: impl AsRef<Path>,
+    ) -> Result<Tables, SnapshotError> {
+        let path = path.as_ref();
+        let snapshot_modified = path.metadata()?.modified()?;
+        let source_modified = latest_source_modification(source_dir.as_ref())?;
+        if is_stale(snapshot_modified, source_modified) {
+            return Err(SnapshotError::Stale)
+        }
+        Tables::load_snapshot(path)
+    }
+
+    /// Decodes tables from the bytes of a snapshot file.
+    pub fn from_snapshot_bytes(bytes: &[u8]) -> Result<Tables, SnapshotError> {
+        if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+            return Err(SnapshotError::NotASnapshot)
+        }
+
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[8..12]);
+        let version = u32::from_le_bytes(version);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found:    version,
+                expected: SNAPSHOT_VERSION,
+            })
+        }
+
+        let expected_digest = &bytes[12..HEADER_LEN];
+        let payload = &bytes[HEADER_LEN..];
+        if Sha256::digest(payload).as_slice() != expected_digest {
+            return Err(SnapshotError::ChecksumMismatch)
+        }
+
+        Ok(bincode::deserialize(payload)?)
+    }
+}
+
+/// Returns whether a snapshot was written before the latest modification of its
+/// source tables, if they have any.
+fn is_stale(snapshot_modified: SystemTime, source_modified: Option<SystemTime>) -> bool {
+    source_modified.map_or(false, |source_modified| source_modified > snapshot_modified)
+}
+
+/// Returns the most recent modification time of any JSONL table or manifest
+/// in `dir`.
+fn latest_source_modification(dir: &Path) -> Result<Option<SystemTime>, std::io::Error> {
+    let mut latest = None;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_source = match path.extension() {
+            Some(extension) => extension == "jsonl" || extension == "json",
+            None => false,
+        };
+        if is_source {
+            let modified = path.metadata()?.modified()?;
+            latest = latest.max(Some(modified));
+        }
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::data::{
+        fixtures::{self, category, game, run},
+        types::*,
+    };
+
+    fn tables() -> Tables {
+        fixtures::tables()
+            .runs(vec![Run {
+                players: vec![RunPlayer::GuestName("guest".to_string())],
+                ..run(3, 0, 1234)
+            }])
+            .games(vec![game(1)])
+            .categories(vec![category(2, 1)])
+            .build()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("tables.bin");
+
+        let tables = tables();
+        tables.write_snapshot(&path).expect("snapshot written");
+        let loaded = Tables::load_snapshot(&path).expect("snapshot loaded");
+
+        assert_eq!(tables.runs(), loaded.runs());
+        assert_eq!(tables.games(), loaded.games());
+        assert_eq!(tables.categories(), loaded.categories());
+    }
+
+    #[test]
+    fn test_snapshot_corruption_detected() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("tables.bin");
+        tables().write_snapshot(&path).expect("snapshot written");
+
+        let mut bytes = std::fs::read(&path).expect("snapshot read");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+
+        match Tables::from_snapshot_bytes(&bytes) {
+            Err(SnapshotError::ChecksumMismatch) => {}
+            other => panic!("expected checksum mismatch, got {:?}", other.map(|_| ())),
+        }
+
+        bytes[8] = 0xFF;
+        match Tables::from_snapshot_bytes(&bytes) {
+            Err(SnapshotError::UnsupportedVersion { .. }) => {}
+            other => panic!("expected unsupported version, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_stale_snapshot_detected() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("tables.bin");
+        let runs_path = dir.path().join("runs.jsonl");
+
+        std::fs::write(&runs_path, "").expect("table written");
+        tables().write_snapshot(&path).expect("snapshot written");
+        Tables::load_fresh_snapshot(&path, dir.path()).expect("fresh snapshot loaded");
+
+        let written = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        assert!(!is_stale(written, None));
+        assert!(!is_stale(written, Some(written - Duration::from_secs(1))));
+        // filesystems with coarse timestamps can give both the same time
+        assert!(!is_stale(written, Some(written)));
+        assert!(is_stale(written, Some(written + Duration::from_millis(1))));
+    }
+}
@@ -0,0 +1,1629 @@
+//! Exports [Tables] to a SQLite database for ad-hoc querying, and imports them
+//! back again.
+//!
+//! Each table gets a SQL table of the same name, plus `game_platforms`,
+//! `game_regions`, and `tagged_games` tables listing each game's platforms,
+//! regions, and tags in order, a `game_assets` table of each game's images, a
+//! `game_series` table listing the series in each game relation in order, a
+//! `game_moderators` table listing each game's moderators in order, a
+//! `run_players` table listing each run's players in order, a
+//! `variable_values` table listing each variable's values in order, a
+//! `run_values` table of each run's variable values, and a `run_videos` table
+//! listing each run's videos in order. Unverified runs have their own
+//! `unverified_run_players`, `unverified_run_values`, and `unverified_run_videos`
+//! tables in the same layout. Enums are stored as their Rust variant names, and
+//! dates and datetimes as ISO 8601 text. User name styles are stored as a style
+//! name and one or two colours.
+//!
+//! Run examiners and game moderators aren't foreign keys, since we don't keep
+//! every user.
+use std::{collections::BTreeMap, convert::TryFrom, path::Path};
+
+use err_derive::Error;
+#[allow(unused)] use log::{debug, error, info, trace, warn};
+use rusqlite::{params, Connection, Row, Statement, NO_PARAMS};
+use tempfile::NamedTempFile;
+
+use crate::data::{database::Tables, types::*};
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+
+    CREATE TABLE platforms (
+        id       INTEGER PRIMARY KEY,
+        slug     TEXT NOT NULL UNIQUE,
+        name     TEXT NOT NULL,
+        released INTEGER NOT NULL
+    );
+
+    CREATE TABLE regions (
+        id   INTEGER PRIMARY KEY,
+        slug TEXT NOT NULL UNIQUE,
+        name TEXT NOT NULL
+    );
+
+    CREATE TABLE game_tags (
+        id   INTEGER PRIMARY KEY,
+        kind TEXT NOT NULL,
+        slug TEXT NOT NULL,
+        name TEXT NOT NULL,
+        UNIQUE (kind, slug)
+    );
+
+    CREATE TABLE games (
+        id             INTEGER PRIMARY KEY,
+        created        TEXT,
+        slug           TEXT NOT NULL UNIQUE,
+        src_slug       TEXT NOT NULL,
+        name           TEXT NOT NULL,
+        primary_timing TEXT NOT NULL,
+        release_date   TEXT,
+        romhack        INTEGER NOT NULL
+    );
+
+    CREATE TABLE game_platforms (
+        game_id     INTEGER NOT NULL REFERENCES games (id),
+        position    INTEGER NOT NULL,
+        platform_id INTEGER NOT NULL REFERENCES platforms (id),
+        PRIMARY KEY (game_id, position)
+    );
+    CREATE INDEX game_platforms_by_platform_id ON game_platforms (platform_id);
+
+    CREATE TABLE game_regions (
+        game_id   INTEGER NOT NULL REFERENCES games (id),
+        position  INTEGER NOT NULL,
+        region_id INTEGER NOT NULL REFERENCES regions (id),
+        PRIMARY KEY (game_id, position)
+    );
+
+    CREATE TABLE tagged_games (
+        game_id  INTEGER NOT NULL REFERENCES games (id),
+        position INTEGER NOT NULL,
+        tag_id   INTEGER NOT NULL REFERENCES game_tags (id),
+        PRIMARY KEY (game_id, position)
+    );
+    CREATE INDEX tagged_games_by_tag_id ON tagged_games (tag_id);
+
+    CREATE TABLE game_assets (
+        game_id INTEGER NOT NULL REFERENCES games (id),
+        name    TEXT NOT NULL,
+        uri     TEXT NOT NULL,
+        width   INTEGER NOT NULL,
+        height  INTEGER NOT NULL,
+        PRIMARY KEY (game_id, name)
+    );
+
+    CREATE TABLE game_relations (
+        game_id      INTEGER PRIMARY KEY REFERENCES games (id),
+        base_game_id INTEGER REFERENCES games (id)
+    );
+    CREATE INDEX game_relations_by_base_game_id ON game_relations (base_game_id);
+
+    CREATE TABLE game_series (
+        game_id   INTEGER NOT NULL REFERENCES game_relations (game_id),
+        position  INTEGER NOT NULL,
+        series_id INTEGER NOT NULL,
+        PRIMARY KEY (game_id, position)
+    );
+    CREATE INDEX game_series_by_series_id ON game_series (series_id);
+
+    CREATE TABLE game_moderators (
+        game_id  INTEGER NOT NULL REFERENCES games (id),
+        position INTEGER NOT NULL,
+        user_id  INTEGER NOT NULL,
+        kind     TEXT NOT NULL,
+        PRIMARY KEY (game_id, position)
+    );
+    CREATE INDEX game_moderators_by_user_id ON game_moderators (user_id);
+
+    CREATE TABLE categories (
+        id      INTEGER PRIMARY KEY,
+        game_id INTEGER NOT NULL REFERENCES games (id),
+        slug    TEXT NOT NULL,
+        name    TEXT NOT NULL,
+        per     TEXT NOT NULL,
+        rules   TEXT NOT NULL
+    );
+    CREATE INDEX categories_by_game_id ON categories (game_id);
+
+    CREATE TABLE levels (
+        id      INTEGER PRIMARY KEY,
+        game_id INTEGER NOT NULL REFERENCES games (id),
+        slug    TEXT NOT NULL,
+        name    TEXT NOT NULL,
+        rules   TEXT NOT NULL
+    );
+    CREATE INDEX levels_by_game_id ON levels (game_id);
+
+    CREATE TABLE variables (
+        id               INTEGER PRIMARY KEY,
+        game_id          INTEGER NOT NULL REFERENCES games (id),
+        category_id      INTEGER REFERENCES categories (id),
+        scope            TEXT NOT NULL,
+        scope_level_id   INTEGER REFERENCES levels (id),
+        slug             TEXT NOT NULL,
+        name             TEXT NOT NULL,
+        is_subcategory   INTEGER NOT NULL,
+        mandatory        INTEGER NOT NULL,
+        obsoletes        INTEGER NOT NULL,
+        default_value_id INTEGER
+    );
+    CREATE INDEX variables_by_game_id ON variables (game_id);
+
+    CREATE TABLE variable_values (
+        variable_id   INTEGER NOT NULL REFERENCES variables (id),
+        position      INTEGER NOT NULL,
+        id            INTEGER NOT NULL,
+        slug          TEXT NOT NULL,
+        label         TEXT NOT NULL,
+        rules         TEXT NOT NULL,
+        miscellaneous INTEGER NOT NULL,
+        PRIMARY KEY (variable_id, position),
+        UNIQUE (variable_id, id)
+    );
+
+    CREATE TABLE users (
+        id               INTEGER PRIMARY KEY,
+        created          TEXT,
+        slug             TEXT NOT NULL UNIQUE,
+        name             TEXT NOT NULL,
+        country_code     TEXT,
+        country          TEXT,
+        region_code      TEXT,
+        region           TEXT,
+        twitch           TEXT,
+        youtube          TEXT,
+        twitter          TEXT,
+        hitbox           TEXT,
+        speedrunslive    TEXT,
+        role             TEXT NOT NULL,
+        name_style       TEXT,
+        color_from_dark  TEXT,
+        color_from_light TEXT,
+        color_to_dark    TEXT,
+        color_to_light   TEXT
+    );
+
+    CREATE TABLE runs (
+        id          INTEGER PRIMARY KEY,
+        game_id     INTEGER NOT NULL REFERENCES games (id),
+        category_id INTEGER NOT NULL REFERENCES categories (id),
+        level_id    INTEGER REFERENCES levels (id),
+        created     TEXT,
+        date        TEXT,
+        igt_ms      INTEGER,
+        rta_ms      INTEGER,
+        rta_nl_ms   INTEGER,
+        comment     TEXT,
+        platform_id INTEGER REFERENCES platforms (id),
+        region_id   INTEGER REFERENCES regions (id),
+        emulated    INTEGER NOT NULL,
+        examiner_id INTEGER,
+        verified    TEXT
+    );
+    CREATE INDEX runs_by_game_id ON runs (game_id);
+    CREATE INDEX runs_by_category_id_and_level_id ON runs (category_id, level_id);
+
+    CREATE TABLE run_players (
+        run_id     INTEGER NOT NULL REFERENCES runs (id),
+        position   INTEGER NOT NULL,
+        user_id    INTEGER REFERENCES users (id),
+        guest_name TEXT,
+        PRIMARY KEY (run_id, position),
+        CHECK ((user_id IS NULL) != (guest_name IS NULL))
+    );
+    CREATE INDEX run_players_by_user_id ON run_players (user_id);
+
+    CREATE TABLE run_values (
+        run_id      INTEGER NOT NULL REFERENCES runs (id),
+        variable_id INTEGER NOT NULL,
+        value_id    INTEGER NOT NULL,
+        PRIMARY KEY (run_id, variable_id),
+        FOREIGN KEY (variable_id, value_id) REFERENCES variable_values (variable_id, id)
+    );
+    CREATE INDEX run_values_by_value_id ON run_values (variable_id, value_id);
+
+    CREATE TABLE run_videos (
+        run_id   INTEGER NOT NULL REFERENCES runs (id),
+        position INTEGER NOT NULL,
+        url      TEXT NOT NULL,
+        platform TEXT NOT NULL,
+        video_id TEXT,
+        start_s  INTEGER,
+        PRIMARY KEY (run_id, position)
+    );
+
+    CREATE TABLE unverified_runs (
+        id          INTEGER PRIMARY KEY,
+        game_id     INTEGER NOT NULL REFERENCES games (id),
+        category_id INTEGER NOT NULL REFERENCES categories (id),
+        level_id    INTEGER REFERENCES levels (id),
+        created     TEXT,
+        date        TEXT,
+        igt_ms      INTEGER,
+        rta_ms      INTEGER,
+        rta_nl_ms   INTEGER,
+        comment     TEXT,
+        platform_id INTEGER REFERENCES platforms (id),
+        region_id   INTEGER REFERENCES regions (id),
+        emulated    INTEGER NOT NULL,
+        examiner_id INTEGER,
+        verified    TEXT,
+        status      TEXT NOT NULL,
+        reason      TEXT
+    );
+    CREATE INDEX unverified_runs_by_game_id ON unverified_runs (game_id);
+
+    CREATE TABLE unverified_run_players (
+        run_id     INTEGER NOT NULL REFERENCES unverified_runs (id),
+        position   INTEGER NOT NULL,
+        user_id    INTEGER REFERENCES users (id),
+        guest_name TEXT,
+        PRIMARY KEY (run_id, position),
+        CHECK ((user_id IS NULL) != (guest_name IS NULL))
+    );
+    CREATE INDEX unverified_run_players_by_user_id ON unverified_run_players (user_id);
+
+    CREATE TABLE unverified_run_values (
+        run_id      INTEGER NOT NULL REFERENCES unverified_runs (id),
+        variable_id INTEGER NOT NULL,
+        value_id    INTEGER NOT NULL,
+        PRIMARY KEY (run_id, variable_id),
+        FOREIGN KEY (variable_id, value_id) REFERENCES variable_values (variable_id, id)
+    );
+
+    CREATE TABLE unverified_run_videos (
+        run_id   INTEGER NOT NULL REFERENCES unverified_runs (id),
+        position INTEGER NOT NULL,
+        url      TEXT NOT NULL,
+        platform TEXT NOT NULL,
+        video_id TEXT,
+        start_s  INTEGER,
+        PRIMARY KEY (run_id, position)
+    );
+";
+
+/// The names of the tables holding runs and their details. Verified and
+/// unverified runs are stored apart, in tables with the same layout.
+struct RunTables {
+    runs:           &'static str,
+    players:        &'static str,
+    values:         &'static str,
+    videos:         &'static str,
+    player_columns: &'static str,
+}
+
+const VERIFIED_RUN_TABLES: RunTables = RunTables {
+    runs:           "runs",
+    players:        "run_players",
+    values:         "run_values",
+    videos:         "run_videos",
+    player_columns: "run_players.user_id, run_players.guest_name",
+};
+
+const UNVERIFIED_RUN_TABLES: RunTables = RunTables {
+    runs:           "unverified_runs",
+    players:        "unverified_run_players",
+    values:         "unverified_run_values",
+    videos:         "unverified_run_videos",
+    player_columns: "unverified_run_players.user_id, unverified_run_players.guest_name",
+};
+
+/// The columns shared by the runs and unverified runs tables, in the order
+/// [RunDetails::take_run] reads them.
+const RUN_COLUMNS: &str = "id, game_id, category_id, level_id, created, date, igt_ms, \
+                           rta_ms, rta_nl_ms, comment, platform_id, region_id, emulated, \
+                           examiner_id, verified";
+
+#[derive(Debug, Error)]
+pub enum SqliteError {
+    #[error(display = "SQLite file I/O failed: {}", _0)]
+    Io(std::io::Error),
+    #[error(display = "SQLite query failed: {}", _0)]
+    Sqlite(rusqlite::Error),
+    #[error(display = "SQLite {} has unexpected value {:?}", column, value)]
+    UnexpectedValue {
+        column: &'static str,
+        value:  String,
+    },
+    #[error(display = "SQLite integer {} is out of range", _0)]
+    OutOfRange(i64),
+}
+
+impl From<std::io::Error> for SqliteError {
+    fn from(error: std::io::Error) -> Self {
+        SqliteError::Io(error)
+    }
+}
+
+impl From<rusqlite::Error> for SqliteError {
+    fn from(error: rusqlite::Error) -> Self {
+        SqliteError::Sqlite(error)
+    }
+}
+
+// SQLite integers are signed. Real IDs, times, and positions are well within
+// range, so one that isn't is a bug when writing, but only bad data when
+// loading.
+
+fn to_sql_id(id: u64) -> i64 {
+    i64::try_from(id).expect("ID to fit in an SQLite integer")
+}
+
+fn from_sql_id(id: i64) -> Result<u64, SqliteError> {
+    u64::try_from(id).map_err(|_| SqliteError::OutOfRange(id))
+}
+
+fn to_sql_time(time_ms: Option<u64>) -> Option<i64> {
+    time_ms.map(|time_ms| i64::try_from(time_ms).expect("time to fit in an SQLite integer"))
+}
+
+fn from_sql_time(time_ms: Option<i64>) -> Result<Option<u64>, SqliteError> {
+    time_ms
+        .map(|time_ms| u64::try_from(time_ms).map_err(|_| SqliteError::OutOfRange(time_ms)))
+        .transpose()
+}
+
+fn to_sql_position(position: usize) -> i64 {
+    i64::try_from(position).expect("position to fit in an SQLite integer")
+}
+
+fn timing_to_sql(timing: &TimingMethod) -> &'static str {
+    match timing {
+        TimingMethod::IGT => "IGT",
+        TimingMethod::RTA => "RTA",
+        TimingMethod::RTA_NL => "RTA_NL",
+    }
+}
+
+fn timing_from_sql(value: String) -> Result<TimingMethod, SqliteError> {
+    match value.as_str() {
+        "IGT" => Ok(TimingMethod::IGT),
+        "RTA" => Ok(TimingMethod::RTA),
+        "RTA_NL" => Ok(TimingMethod::RTA_NL),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "games.primary_timing",
+            value,
+        }),
+    }
+}
+
+fn per_to_sql(per: &CategoryType) -> &'static str {
+    match per {
+        CategoryType::PerGame => "PerGame",
+        CategoryType::PerLevel => "PerLevel",
+    }
+}
+
+fn per_from_sql(value: String) -> Result<CategoryType, SqliteError> {
+    match value.as_str() {
+        "PerGame" => Ok(CategoryType::PerGame),
+        "PerLevel" => Ok(CategoryType::PerLevel),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "categories.per",
+            value,
+        }),
+    }
+}
+
+fn platform_to_sql(platform: &VideoPlatform) -> &'static str {
+    match platform {
+        VideoPlatform::YouTube => "YouTube",
+        VideoPlatform::TwitchVod => "TwitchVod",
+        VideoPlatform::Other => "Other",
+    }
+}
+
+fn platform_from_sql(value: String) -> Result<VideoPlatform, SqliteError> {
+    match value.as_str() {
+        "YouTube" => Ok(VideoPlatform::YouTube),
+        "TwitchVod" => Ok(VideoPlatform::TwitchVod),
+        "Other" => Ok(VideoPlatform::Other),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "run_videos.platform",
+            value,
+        }),
+    }
+}
+
+fn tag_kind_to_sql(kind: GameTagKind) -> &'static str {
+    match kind {
+        GameTagKind::Genre => "Genre",
+        GameTagKind::Developer => "Developer",
+        GameTagKind::Publisher => "Publisher",
+        GameTagKind::Engine => "Engine",
+        GameTagKind::GameType => "GameType",
+    }
+}
+
+fn tag_kind_from_sql(value: String) -> Result<GameTagKind, SqliteError> {
+    match value.as_str() {
+        "Genre" => Ok(GameTagKind::Genre),
+        "Developer" => Ok(GameTagKind::Developer),
+        "Publisher" => Ok(GameTagKind::Publisher),
+        "Engine" => Ok(GameTagKind::Engine),
+        "GameType" => Ok(GameTagKind::GameType),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "game_tags.kind",
+            value,
+        }),
+    }
+}
+
+fn moderator_kind_to_sql(kind: ModeratorKind) -> &'static str {
+    match kind {
+        ModeratorKind::Moderator => "Moderator",
+        ModeratorKind::SuperModerator => "SuperModerator",
+    }
+}
+
+fn moderator_kind_from_sql(value: String) -> Result<ModeratorKind, SqliteError> {
+    match value.as_str() {
+        "Moderator" => Ok(ModeratorKind::Moderator),
+        "SuperModerator" => Ok(ModeratorKind::SuperModerator),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "game_moderators.kind",
+            value,
+        }),
+    }
+}
+
+fn status_to_sql(status: ModerationStatus) -> &'static str {
+    match status {
+        ModerationStatus::Pending => "Pending",
+        ModerationStatus::Rejected => "Rejected",
+    }
+}
+
+fn status_from_sql(value: String) -> Result<ModerationStatus, SqliteError> {
+    match value.as_str() {
+        "Pending" => Ok(ModerationStatus::Pending),
+        "Rejected" => Ok(ModerationStatus::Rejected),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "unverified_runs.status",
+            value,
+        }),
+    }
+}
+
+/// Returns each of a game's assets that's present, with the name it's stored
+/// under.
+fn assets_to_sql(assets: &GameAssets) -> Vec<(&'static str, &GameAsset)> {
+    vec![
+        ("cover", &assets.cover),
+        ("icon", &assets.icon),
+        ("logo", &assets.logo),
+        ("background", &assets.background),
+    ]
+    .into_iter()
+    .filter_map(|(name, asset)| asset.as_ref().map(|asset| (name, asset)))
+    .collect()
+}
+
+fn asset_slot<'a>(
+    assets: &'a mut GameAssets,
+    name: String,
+) -> Result<&'a mut Option<GameAsset>, SqliteError> {
+    match name.as_str() {
+        "cover" => Ok(&mut assets.cover),
+        "icon" => Ok(&mut assets.icon),
+        "logo" => Ok(&mut assets.logo),
+        "background" => Ok(&mut assets.background),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "game_assets.name",
+            value:  name,
+        }),
+    }
+}
+
+fn role_to_sql(role: &UserRole) -> &'static str {
+    match role {
+        UserRole::User => "User",
+        UserRole::Trusted => "Trusted",
+        UserRole::Moderator => "Moderator",
+        UserRole::ContentModerator => "ContentModerator",
+        UserRole::Admin => "Admin",
+        UserRole::Programmer => "Programmer",
+        UserRole::Banned => "Banned",
+    }
+}
+
+fn role_from_sql(value: String) -> Result<UserRole, SqliteError> {
+    match value.as_str() {
+        "User" => Ok(UserRole::User),
+        "Trusted" => Ok(UserRole::Trusted),
+        "Moderator" => Ok(UserRole::Moderator),
+        "ContentModerator" => Ok(UserRole::ContentModerator),
+        "Admin" => Ok(UserRole::Admin),
+        "Programmer" => Ok(UserRole::Programmer),
+        "Banned" => Ok(UserRole::Banned),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "users.role",
+            value,
+        }),
+    }
+}
+
+/// Returns the style name and the colours it goes from and to. Solid styles
+/// only have a from colour.
+fn name_style_to_sql(
+    style: &Option<UserNameStyle>,
+) -> (Option<&'static str>, Option<&UserColor>, Option<&UserColor>) {
+    match style {
+        None => (None, None, None),
+        Some(UserNameStyle::Solid(color)) => (Some("Solid"), Some(color), None),
+        Some(UserNameStyle::Gradient { from, to }) =>
+            (Some("Gradient"), Some(from), Some(to)),
+    }
+}
+
+fn name_style_from_sql(
+    value: Option<String>,
+    from: Option<UserColor>,
+    to: Option<UserColor>,
+) -> Result<Option<UserNameStyle>, SqliteError> {
+    match (value.as_deref(), from, to) {
+        (None, None, None) => Ok(None),
+        (Some("Solid"), Some(color), None) => Ok(Some(UserNameStyle::Solid(color))),
+        (Some("Gradient"), Some(from), Some(to)) =>
+            Ok(Some(UserNameStyle::Gradient { from, to })),
+        (_, from, to) => Err(SqliteError::UnexpectedValue {
+            column: "users.name_style, users.color_*",
+            value:  format!("{:?}, {:?}, {:?}", value, from, to),
+        }),
+    }
+}
+
+fn color_from_sql(dark: Option<String>, light: Option<String>) -> Option<UserColor> {
+    match (dark, light) {
+        (Some(dark), Some(light)) => Some(UserColor { dark, light }),
+        _ => None,
+    }
+}
+
+fn scope_to_sql(scope: &VariableScope) -> (&'static str, Option<i64>) {
+    match scope {
+        VariableScope::Global => ("Global", None),
+        VariableScope::FullGame => ("FullGame", None),
+        VariableScope::AllLevels => ("AllLevels", None),
+        VariableScope::SingleLevel(level_id) => ("SingleLevel", Some(to_sql_id(*level_id))),
+    }
+}
+
+fn scope_from_sql(
+    value: String,
+    level_id: Option<i64>,
+) -> Result<VariableScope, SqliteError> {
+    match (value.as_str(), level_id) {
+        ("Global", None) => Ok(VariableScope::Global),
+        ("FullGame", None) => Ok(VariableScope::FullGame),
+        ("AllLevels", None) => Ok(VariableScope::AllLevels),
+        ("SingleLevel", Some(level_id)) =>
+            Ok(VariableScope::SingleLevel(from_sql_id(level_id)?)),
+        _ => Err(SqliteError::UnexpectedValue {
+            column: "variables.scope, variables.scope_level_id",
+            value:  format!("{:?}, {:?}", value, level_id),
+        }),
+    }
+}
+
+impl Tables {
+    /// Writes these tables to a new SQLite database at `path`, atomically
+    /// replacing any existing file.
+    pub fn write_sqlite(&self, path: impl AsRef<Path>) -> Result<(), SqliteError> {
+        let path = path.as_ref();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let file = NamedTempFile::new_in(dir)?;
+
+        {
+            let mut connection = Connection::open(file.path())?;
+            connection.execute_batch(SCHEMA)?;
+            let transaction = connection.transaction()?;
+
+            {
+                let mut insert = transaction.prepare(
+                    "INSERT INTO platforms (id, slug, name, released) VALUES (?, ?, ?, ?)",
+                )?;
+                for platform in self.platforms().values() {
+                    insert.execute(params![
+                        to_sql_id(platform.id),
+                        platform.slug,
+                        platform.name,
+                        platform.released,
+                    ])?;
+                }
+
+                let mut insert = transaction
+                    .prepare("INSERT INTO regions (id, slug, name) VALUES (?, ?, ?)")?;
+                for region in self.regions().values() {
+                    insert.execute(params![
+                        to_sql_id(region.id),
+                        region.slug,
+                        region.name,
+                    ])?;
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO game_tags (id, kind, slug, name) VALUES (?, ?, ?, ?)",
+                )?;
+                for tag in self.game_tags().values() {
+                    insert.execute(params![
+                        to_sql_id(tag.id),
+                        tag_kind_to_sql(tag.kind),
+                        tag.slug,
+                        tag.name,
+                    ])?;
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO games (id, created, slug, src_slug, name, primary_timing,
+                                        release_date, romhack)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                let mut insert_platform = transaction.prepare(
+                    "INSERT INTO game_platforms (game_id, position, platform_id)
+                     VALUES (?, ?, ?)",
+                )?;
+                let mut insert_region = transaction.prepare(
+                    "INSERT INTO game_regions (game_id, position, region_id) VALUES (?, ?, ?)",
+                )?;
+                let mut insert_tag = transaction.prepare(
+                    "INSERT INTO tagged_games (game_id, position, tag_id) VALUES (?, ?, ?)",
+                )?;
+                let mut insert_asset = transaction.prepare(
+                    "INSERT INTO game_assets (game_id, name, uri, width, height)
+                     VALUES (?, ?, ?, ?, ?)",
+                )?;
+                let mut insert_moderator = transaction.prepare(
+                    "INSERT INTO game_moderators (game_id, position, user_id, kind)
+                     VALUES (?, ?, ?, ?)",
+                )?;
+                for game in self.games().values() {
+                    insert.execute(params![
+                        to_sql_id(game.id),
+                        game.created,
+                        game.slug,
+                        game.src_slug,
+                        game.name,
+                        timing_to_sql(&game.primary_timing),
+                        game.release_date,
+                        game.romhack,
+                    ])?;
+                    for (position, platform_id) in game.platform_ids.iter().enumerate() {
+                        insert_platform.execute(params![
+                            to_sql_id(game.id),
+                            to_sql_position(position),
+                            to_sql_id(*platform_id),
+                        ])?;
+                    }
+                    for (position, region_id) in game.region_ids.iter().enumerate() {
+                        insert_region.execute(params![
+                            to_sql_id(game.id),
+                            to_sql_position(position),
+                            to_sql_id(*region_id),
+                        ])?;
+                    }
+                    for (position, tag_id) in game.tag_ids.iter().enumerate() {
+                        insert_tag.execute(params![
+                            to_sql_id(game.id),
+                            to_sql_position(position),
+                            to_sql_id(*tag_id),
+                        ])?;
+                    }
+                    for (name, asset) in assets_to_sql(&game.assets) {
+                        insert_asset.execute(params![
+                            to_sql_id(game.id),
+                            name,
+                            asset.uri,
+                            asset.width,
+                            asset.height,
+                        ])?;
+                    }
+                    for (position, moderator) in game.moderators.iter().enumerate() {
+                        insert_moderator.execute(params![
+                            to_sql_id(game.id),
+                            to_sql_position(position),
+                            to_sql_id(moderator.user_id),
+                            moderator_kind_to_sql(moderator.kind),
+                        ])?;
+                    }
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO game_relations (game_id, base_game_id) VALUES (?, ?)",
+                )?;
+                let mut insert_series = transaction.prepare(
+                    "INSERT INTO game_series (game_id, position, series_id) VALUES (?, ?, ?)",
+                )?;
+                for relation in self.game_relations().values() {
+                    insert.execute(params![
+                        to_sql_id(relation.game_id),
+                        relation.base_game_id.map(to_sql_id),
+                    ])?;
+                    for (position, series_id) in relation.series_ids.iter().enumerate() {
+                        insert_series.execute(params![
+                            to_sql_id(relation.game_id),
+                            to_sql_position(position),
+                            to_sql_id(*series_id),
+                        ])?;
+                    }
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO categories (id, game_id, slug, name, per, rules)
+                     VALUES (?, ?, ?, ?, ?, ?)",
+                )?;
+                for category in self.categories().values() {
+                    insert.execute(params![
+                        to_sql_id(category.id),
+                        to_sql_id(category.game_id),
+                        category.slug,
+                        category.name,
+                        per_to_sql(&category.per),
+                        category.rules,
+                    ])?;
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO levels (id, game_id, slug, name, rules)
+                     VALUES (?, ?, ?, ?, ?)",
+                )?;
+                for level in self.levels().values() {
+                    insert.execute(params![
+                        to_sql_id(level.id),
+                        to_sql_id(level.game_id),
+                        level.slug,
+                        level.name,
+                        level.rules,
+                    ])?;
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO variables (id, game_id, category_id, scope, scope_level_id,
+                                            slug, name, is_subcategory, mandatory,
+                                            obsoletes, default_value_id)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                let mut insert_value = transaction.prepare(
+                    "INSERT INTO variable_values (variable_id, position, id, slug, label,
+                                                  rules, miscellaneous)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                for variable in self.variables().values() {
+                    let (scope, scope_level_id) = scope_to_sql(&variable.scope);
+                    insert.execute(params![
+                        to_sql_id(variable.id),
+                        to_sql_id(variable.game_id),
+                        variable.category_id.map(to_sql_id),
+                        scope,
+                        scope_level_id,
+                        variable.slug,
+                        variable.name,
+                        variable.is_subcategory,
+                        variable.mandatory,
+                        variable.obsoletes,
+                        variable.default_value_id.map(to_sql_id),
+                    ])?;
+                    for (position, value) in variable.values.iter().enumerate() {
+                        insert_value.execute(params![
+                            to_sql_id(variable.id),
+                            to_sql_position(position),
+                            to_sql_id(value.id),
+                            value.slug,
+                            value.label,
+                            value.rules,
+                            value.miscellaneous,
+                        ])?;
+                    }
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO users (id, created, slug, name, country_code, country,
+                                        region_code, region, twitch, youtube, twitter,
+                                        hitbox, speedrunslive, role, name_style,
+                                        color_from_dark, color_from_light, color_to_dark,
+                                        color_to_light)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                for user in self.users().values() {
+                    let location = user.location.as_ref();
+                    let (name_style, from, to) = name_style_to_sql(&user.name_style);
+                    insert.execute(params![
+                        to_sql_id(user.id),
+                        user.created,
+                        user.slug,
+                        user.name,
+                        location.and_then(|location| location.country_code.as_ref()),
+                        location.map(|location| &location.country),
+                        location.and_then(|location| location.region_code.as_ref()),
+                        location.and_then(|location| location.region.as_ref()),
+                        user.links.twitch,
+                        user.links.youtube,
+                        user.links.twitter,
+                        user.links.hitbox,
+                        user.links.speedrunslive,
+                        role_to_sql(&user.role),
+                        name_style,
+                        from.map(|color| &color.dark),
+                        from.map(|color| &color.light),
+                        to.map(|color| &color.dark),
+                        to.map(|color| &color.light),
+                    ])?;
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO runs (id, game_id, category_id, level_id, created, date,
+                                       igt_ms, rta_ms, rta_nl_ms, comment, platform_id,
+                                       region_id, emulated, examiner_id, verified)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                let mut insert_details =
+                    RunDetailInserts::prepare(&transaction, &VERIFIED_RUN_TABLES)?;
+                for run in self.runs().values() {
+                    insert.execute(params![
+                        to_sql_id(run.id),
+                        to_sql_id(run.game_id),
+                        to_sql_id(run.category_id),
+                        run.level_id.map(to_sql_id),
+                        run.created,
+                        run.date,
+                        to_sql_time(run.times_ms.igt),
+                        to_sql_time(run.times_ms.rta),
+                        to_sql_time(run.times_ms.rta_nl),
+                        run.comment,
+                        run.platform_id.map(to_sql_id),
+                        run.region_id.map(to_sql_id),
+                        run.emulated,
+                        run.examiner_id.map(to_sql_id),
+                        run.verified,
+                    ])?;
+                    insert_details.insert(run)?;
+                }
+
+                let mut insert = transaction.prepare(
+                    "INSERT INTO unverified_runs (id, game_id, category_id, level_id, created,
+                                                  date, igt_ms, rta_ms, rta_nl_ms, comment,
+                                                  platform_id, region_id, emulated,
+                                                  examiner_id, verified, status, reason)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )?;
+                let mut insert_details =
+                    RunDetailInserts::prepare(&transaction, &UNVERIFIED_RUN_TABLES)?;
+                for unverified in self.unverified_runs().values() {
+                    let run = &unverified.run;
+                    insert.execute(params![
+                        to_sql_id(run.id),
+                        to_sql_id(run.game_id),
+                        to_sql_id(run.category_id),
+                        run.level_id.map(to_sql_id),
+                        run.created,
+                        run.date,
+                        to_sql_time(run.times_ms.igt),
+                        to_sql_time(run.times_ms.rta),
+                        to_sql_time(run.times_ms.rta_nl),
+                        run.comment,
+                        run.platform_id.map(to_sql_id),
+                        run.region_id.map(to_sql_id),
+                        run.emulated,
+                        run.examiner_id.map(to_sql_id),
+                        run.verified,
+                        status_to_sql(unverified.status),
+                        unverified.reason,
+                    ])?;
+                    insert_details.insert(run)?;
+                }
+            }
+
+            transaction.commit()?;
+        }
+
+        file.persist(path).map_err(|error| error.error)?;
+
+        Ok(())
+    }
+
+    /// Loads tables from a SQLite database in the format written by
+    /// [Tables::write_sqlite].
+    ///
+    /// The tables are not validated; use [crate::data::database::Database] or
+    /// [Tables::repair] for that.
+    pub fn load_sqlite(path: impl AsRef<Path>) -> Result<Tables, SqliteError> {
+        let connection = Connection::open(path)?;
+
+        let mut platforms = Vec::new();
+        let mut query =
+            connection.prepare("SELECT id, slug, name, released FROM platforms")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            platforms.push(Platform {
+                id:       from_sql_id(row.get(0)?)?,
+                slug:     row.get(1)?,
+                name:     row.get(2)?,
+                released: row.get(3)?,
+            });
+        }
+
+        let mut regions = Vec::new();
+        let mut query = connection.prepare("SELECT id, slug, name FROM regions")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            regions.push(Region {
+                id:   from_sql_id(row.get(0)?)?,
+                slug: row.get(1)?,
+                name: row.get(2)?,
+            });
+        }
+
+        let mut platform_ids_by_game_id = BTreeMap::<u64, Vec<u64>>::new();
+        let mut query = connection.prepare(
+            "SELECT game_id, platform_id FROM game_platforms ORDER BY game_id, position",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            platform_ids_by_game_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(from_sql_id(row.get(1)?)?);
+        }
+
+        let mut region_ids_by_game_id = BTreeMap::<u64, Vec<u64>>::new();
+        let mut query = connection.prepare(
+            "SELECT game_id, region_id FROM game_regions ORDER BY game_id, position",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            region_ids_by_game_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(from_sql_id(row.get(1)?)?);
+        }
+
+        let mut game_tags = Vec::new();
+        let mut query = connection.prepare("SELECT id, kind, slug, name FROM game_tags")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            game_tags.push(GameTag {
+                id:   from_sql_id(row.get(0)?)?,
+                kind: tag_kind_from_sql(row.get(1)?)?,
+                slug: row.get(2)?,
+                name: row.get(3)?,
+            });
+        }
+
+        let mut tag_ids_by_game_id = BTreeMap::<u64, Vec<u64>>::new();
+        let mut query = connection.prepare(
+            "SELECT game_id, tag_id FROM tagged_games ORDER BY game_id, position",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            tag_ids_by_game_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(from_sql_id(row.get(1)?)?);
+        }
+
+        let mut assets_by_game_id = BTreeMap::<u64, GameAssets>::new();
+        let mut query = connection
+            .prepare("SELECT game_id, name, uri, width, height FROM game_assets")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let assets = assets_by_game_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(GameAssets::default);
+            *asset_slot(assets, row.get(1)?)? = Some(GameAsset {
+                uri:    row.get(2)?,
+                width:  row.get(3)?,
+                height: row.get(4)?,
+            });
+        }
+
+        let mut moderators_by_game_id = BTreeMap::<u64, Vec<GameModerator>>::new();
+        let mut query = connection.prepare(
+            "SELECT game_id, user_id, kind FROM game_moderators ORDER BY game_id, position",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            moderators_by_game_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(GameModerator {
+                    user_id: from_sql_id(row.get(1)?)?,
+                    kind:    moderator_kind_from_sql(row.get(2)?)?,
+                });
+        }
+
+        let mut games = Vec::new();
+        let mut query = connection.prepare(
+            "SELECT id, created, slug, src_slug, name, primary_timing, release_date, romhack
+             FROM games",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let id = from_sql_id(row.get(0)?)?;
+            games.push(Game {
+                id,
+                created: row.get(1)?,
+                slug: row.get(2)?,
+                src_slug: row.get(3)?,
+                name: row.get(4)?,
+                primary_timing: timing_from_sql(row.get(5)?)?,
+                platform_ids: platform_ids_by_game_id.remove(&id).unwrap_or_default(),
+                region_ids: region_ids_by_game_id.remove(&id).unwrap_or_default(),
+                release_date: row.get(6)?,
+                romhack: row.get(7)?,
+                tag_ids: tag_ids_by_game_id.remove(&id).unwrap_or_default(),
+                assets: assets_by_game_id.remove(&id).unwrap_or_default(),
+                moderators: moderators_by_game_id.remove(&id).unwrap_or_default(),
+            });
+        }
+
+        let mut series_ids_by_game_id = BTreeMap::<u64, Vec<u64>>::new();
+        let mut query = connection.prepare(
+            "SELECT game_id, series_id FROM game_series ORDER BY game_id, position",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            series_ids_by_game_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(from_sql_id(row.get(1)?)?);
+        }
+
+        let mut game_relations = Vec::new();
+        let mut query =
+            connection.prepare("SELECT game_id, base_game_id FROM game_relations")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let game_id = from_sql_id(row.get(0)?)?;
+            let base_game_id: Option<i64> = row.get(1)?;
+            game_relations.push(GameRelation {
+                game_id,
+                base_game_id: base_game_id.map(from_sql_id).transpose()?,
+                series_ids: series_ids_by_game_id.remove(&game_id).unwrap_or_default(),
+            });
+        }
+
+        let mut categories = Vec::new();
+        let mut query = connection
+            .prepare("SELECT id, game_id, slug, name, per, rules FROM categories")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            categories.push(Category {
+                id:      from_sql_id(row.get(0)?)?,
+                game_id: from_sql_id(row.get(1)?)?,
+                slug:    row.get(2)?,
+                name:    row.get(3)?,
+                per:     per_from_sql(row.get(4)?)?,
+                rules:   row.get(5)?,
+            });
+        }
+
+        let mut levels = Vec::new();
+        let mut query =
+            connection.prepare("SELECT id, game_id, slug, name, rules FROM levels")?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            levels.push(Level {
+                id:      from_sql_id(row.get(0)?)?,
+                game_id: from_sql_id(row.get(1)?)?,
+                slug:    row.get(2)?,
+                name:    row.get(3)?,
+                rules:   row.get(4)?,
+            });
+        }
+
+        let mut values_by_variable_id = BTreeMap::<u64, Vec<VariableValue>>::new();
+        let mut query = connection.prepare(
+            "SELECT variable_id, id, slug, label, rules, miscellaneous
+             FROM variable_values ORDER BY variable_id, position",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            values_by_variable_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(VariableValue {
+                    id:            from_sql_id(row.get(1)?)?,
+                    slug:          row.get(2)?,
+                    label:         row.get(3)?,
+                    rules:         row.get(4)?,
+                    miscellaneous: row.get(5)?,
+                });
+        }
+
+        let mut variables = Vec::new();
+        let mut query = connection.prepare(
+            "SELECT id, game_id, category_id, scope, scope_level_id, slug, name,
+                    is_subcategory, mandatory, obsoletes, default_value_id
+             FROM variables",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let id = from_sql_id(row.get(0)?)?;
+            let category_id: Option<i64> = row.get(2)?;
+            let default_value_id: Option<i64> = row.get(10)?;
+            variables.push(Variable {
+                id,
+                game_id: from_sql_id(row.get(1)?)?,
+                category_id: category_id.map(from_sql_id).transpose()?,
+                scope: scope_from_sql(row.get(3)?, row.get(4)?)?,
+                slug: row.get(5)?,
+                name: row.get(6)?,
+                is_subcategory: row.get(7)?,
+                mandatory: row.get(8)?,
+                obsoletes: row.get(9)?,
+                default_value_id: default_value_id.map(from_sql_id).transpose()?,
+                values: values_by_variable_id.remove(&id).unwrap_or_default(),
+            });
+        }
+
+        let mut users = Vec::new();
+        let mut query = connection.prepare(
+            "SELECT id, created, slug, name, country_code, country, region_code, region,
+                    twitch, youtube, twitter, hitbox, speedrunslive, role, name_style,
+                    color_from_dark, color_from_light, color_to_dark, color_to_light
+             FROM users",
+        )?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let country: Option<String> = row.get(5)?;
+            users.push(User {
+                id:         from_sql_id(row.get(0)?)?,
+                created:    row.get(1)?,
+                slug:       row.get(2)?,
+                name:       row.get(3)?,
+                location:   match country {
+                    Some(country) => Some(UserLocation {
+                        country_code: row.get(4)?,
+                        country,
+                        region_code: row.get(6)?,
+                        region: row.get(7)?,
+                    }),
+                    None => None,
+                },
+                links:      UserLinks {
+                    twitch:        row.get(8)?,
+                    youtube:       row.get(9)?,
+                    twitter:       row.get(10)?,
+                    hitbox:        row.get(11)?,
+                    speedrunslive: row.get(12)?,
+                },
+                role:       role_from_sql(row.get(13)?)?,
+                name_style: name_style_from_sql(
+                    row.get(14)?,
+                    color_from_sql(row.get(15)?, row.get(16)?),
+                    color_from_sql(row.get(17)?, row.get(18)?),
+                )?,
+            });
+        }
+
+        let mut details = RunDetails::load(&connection, &VERIFIED_RUN_TABLES)?;
+        let mut runs = Vec::new();
+        let mut query = connection.prepare(&format!("SELECT {} FROM runs", RUN_COLUMNS))?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            runs.push(details.take_run(row)?);
+        }
+
+        let mut details = RunDetails::load(&connection, &UNVERIFIED_RUN_TABLES)?;
+        let mut unverified_runs = Vec::new();
+        let mut query = connection.prepare(&format!(
+            "SELECT {}, status, reason FROM unverified_runs",
+            RUN_COLUMNS
+        ))?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            unverified_runs.push(UnverifiedRun {
+                run:    details.take_run(row)?,
+                status: status_from_sql(row.get(15)?)?,
+                reason: row.get(16)?,
+            });
+        }
+
+        Ok(Tables::new(
+            runs,
+            users,
+            games,
+            categories,
+            levels,
+            variables,
+            platforms,
+            regions,
+            game_tags,
+            game_relations,
+            unverified_runs,
+        ))
+    }
+}
+
+/// Prepared statements inserting runs' players, values, and videos.
+struct RunDetailInserts<'a> {
+    players: Statement<'a>,
+    values:  Statement<'a>,
+    videos:  Statement<'a>,
+}
+
+impl<'a> RunDetailInserts<'a> {
+    fn prepare(
+        connection: &'a Connection,
+        tables: &RunTables,
+    ) -> Result<Self, SqliteError> {
+        Ok(Self {
+            players: connection.prepare(&format!(
+                "INSERT INTO {} (run_id, position, user_id, guest_name) VALUES (?, ?, ?, ?)",
+                tables.players
+            ))?,
+            values:  connection.prepare(&format!(
+                "INSERT INTO {} (run_id, variable_id, value_id) VALUES (?, ?, ?)",
+                tables.values
+            ))?,
+            videos:  connection.prepare(&format!(
+                "INSERT INTO {} (run_id, position, url, platform, video_id, start_s)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+                tables.videos
+            ))?,
+        })
+    }
+
+    fn insert(&mut self, run: &Run) -> Result<(), SqliteError> {
+        for (position, player) in run.players.iter().enumerate() {
+            let (user_id, guest_name) = match player {
+                RunPlayer::UserId(user_id) => (Some(to_sql_id(*user_id)), None),
+                RunPlayer::GuestName(name) => (None, Some(name)),
+            };
+            self.players.execute(params![
+                to_sql_id(run.id),
+                to_sql_position(position),
+                user_id,
+                guest_name,
+            ])?;
+        }
+        for (variable_id, value_id) in run.values.iter() {
+            self.values.execute(params![
+                to_sql_id(run.id),
+                to_sql_id(*variable_id),
+                to_sql_id(*value_id),
+            ])?;
+        }
+        for (position, video) in run.videos.iter().enumerate() {
+            self.videos.execute(params![
+                to_sql_id(run.id),
+                to_sql_position(position),
+                video.url,
+                platform_to_sql(&video.platform),
+                video.video_id,
+                to_sql_time(video.start_s),
+            ])?;
+        }
+        Ok(())
+    }
+}
+
+/// The players, values, and videos of every run in one of the run tables, by
+/// run ID, waiting to be attached to their runs.
+struct RunDetails {
+    players_by_run_id: BTreeMap<u64, Vec<RunPlayer>>,
+    values_by_run_id:  BTreeMap<u64, BTreeMap<u64, u64>>,
+    videos_by_run_id:  BTreeMap<u64, Vec<RunVideo>>,
+}
+
+impl RunDetails {
+    fn load(connection: &Connection, tables: &RunTables) -> Result<Self, SqliteError> {
+        let mut players_by_run_id = BTreeMap::<u64, Vec<RunPlayer>>::new();
+        let mut query = connection.prepare(&format!(
+            "SELECT run_id, user_id, guest_name FROM {} ORDER BY run_id, position",
+            tables.players
+        ))?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let run_id = from_sql_id(row.get(0)?)?;
+            let user_id: Option<i64> = row.get(1)?;
+            let guest_name: Option<String> = row.get(2)?;
+            let player = match (user_id, guest_name) {
+                (Some(user_id), None) => RunPlayer::UserId(from_sql_id(user_id)?),
+                (None, Some(name)) => RunPlayer::GuestName(name),
+                (user_id, guest_name) =>
+                    return Err(SqliteError::UnexpectedValue {
+                        column: tables.player_columns,
+                        value:  format!("{:?}, {:?}", user_id, guest_name),
+                    }),
+            };
+            players_by_run_id
+                .entry(run_id)
+                .or_insert_with(Vec::new)
+                .push(player);
+        }
+
+        let mut values_by_run_id = BTreeMap::<u64, BTreeMap<u64, u64>>::new();
+        let mut query = connection.prepare(&format!(
+            "SELECT run_id, variable_id, value_id FROM {}",
+            tables.values
+        ))?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            values_by_run_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(BTreeMap::new)
+                .insert(from_sql_id(row.get(1)?)?, from_sql_id(row.get(2)?)?);
+        }
+
+        let mut videos_by_run_id = BTreeMap::<u64, Vec<RunVideo>>::new();
+        let mut query = connection.prepare(&format!(
+            "SELECT run_id, url, platform, video_id, start_s
+             FROM {} ORDER BY run_id, position",
+            tables.videos
+        ))?;
+        let mut rows = query.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            videos_by_run_id
+                .entry(from_sql_id(row.get(0)?)?)
+                .or_insert_with(Vec::new)
+                .push(RunVideo {
+                    url:      row.get(1)?,
+                    platform: platform_from_sql(row.get(2)?)?,
+                    video_id: row.get(3)?,
+                    start_s:  from_sql_time(row.get(4)?)?,
+                });
+        }
+
+        Ok(Self {
+            players_by_run_id,
+            values_by_run_id,
+            videos_by_run_id,
+        })
+    }
+
+    /// Builds a run from a row starting with the [RUN_COLUMNS], taking its
+    /// details.
+    fn take_run(&mut self, row: &Row<'_>) -> Result<Run, SqliteError> {
+        let id = from_sql_id(row.get(0)?)?;
+        let level_id: Option<i64> = row.get(3)?;
+        let platform_id: Option<i64> = row.get(10)?;
+        let region_id: Option<i64> = row.get(11)?;
+        let examiner_id: Option<i64> = row.get(13)?;
+        Ok(Run {
+            id,
+            game_id: from_sql_id(row.get(1)?)?,
+            category_id: from_sql_id(row.get(2)?)?,
+            level_id: level_id.map(from_sql_id).transpose()?,
+            created: row.get(4)?,
+            date: row.get(5)?,
+            times_ms: RunTimesMs {
+                igt:    from_sql_time(row.get(6)?)?,
+                rta:    from_sql_time(row.get(7)?)?,
+                rta_nl: from_sql_time(row.get(8)?)?,
+            },
+            players: self.players_by_run_id.remove(&id).unwrap_or_default(),
+            values: self.values_by_run_id.remove(&id).unwrap_or_default(),
+            videos: self.videos_by_run_id.remove(&id).unwrap_or_default(),
+            comment: row.get(9)?,
+            platform_id: platform_id.map(from_sql_id).transpose()?,
+            region_id: region_id.map(from_sql_id).transpose()?,
+            emulated: row.get(12)?,
+            examiner_id: examiner_id.map(from_sql_id).transpose()?,
+            verified: row.get(14)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn test_sqlite_round_trip() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("speedruns.sqlite");
+
+        let tables = Tables::new(
+            vec![Run {
+                game_id:     1,
+                category_id: 2,
+                level_id:    Some(3),
+                id:          4,
+                created:     None,
+                date:        Some(NaiveDate::from_ymd(2019, 12, 31)),
+                times_ms:    RunTimesMs {
+                    igt:    Some(1000),
+                    rta:    Some(1234),
+                    rta_nl: None,
+                },
+                players:     vec![
+                    RunPlayer::UserId(5),
+                    RunPlayer::GuestName("guest".to_string()),
+                ],
+                values:      vec![(6, 8)].into_iter().collect(),
+                videos:      vec![RunVideo {
+                    url:      "https://youtu.be/dQw4w9WgXcQ?t=5".to_string(),
+                    platform: VideoPlatform::YouTube,
+                    video_id: Some("dQw4w9WgXcQ".to_string()),
+                    start_s:  Some(5),
+                }],
+                comment:     Some("GG".to_string()),
+                platform_id: Some(9),
+                region_id:   None,
+                emulated:    true,
+                examiner_id: Some(15),
+                verified:    Some(Utc.ymd(2020, 1, 2).and_hms(3, 4, 5)),
+            }],
+            vec![User {
+                id:         5,
+                created:    None,
+                slug:       "user".to_string(),
+                name:       "User".to_string(),
+                location:   Some(UserLocation {
+                    country_code: Some("ca".to_string()),
+                    country:      "Canada".to_string(),
+                    region_code:  None,
+                    region:       None,
+                }),
+                links:      UserLinks {
+                    twitch: Some("https://www.twitch.tv/user".to_string()),
+                    ..UserLinks::default()
+                },
+                role:       UserRole::Trusted,
+                name_style: Some(UserNameStyle::Gradient {
+                    from: UserColor {
+                        dark:  "#EE4444".to_string(),
+                        light: "#CC2222".to_string(),
+                    },
+                    to:   UserColor {
+                        dark:  "#4444EE".to_string(),
+                        light: "#2222CC".to_string(),
+                    },
+                }),
+            }],
+            vec![Game {
+                id:             1,
+                created:        None,
+                slug:           "game".to_string(),
+                src_slug:       "game".to_string(),
+                name:           "Game".to_string(),
+                primary_timing: TimingMethod::RTA_NL,
+                platform_ids:   vec![9],
+                region_ids:     vec![10],
+                release_date:   Some(NaiveDate::from_ymd(1988, 10, 23)),
+                romhack:        true,
+                tag_ids:        vec![12, 11],
+                assets:         GameAssets {
+                    cover: Some(GameAsset {
+                        uri:    "https://www.speedrun.com/themes/game/cover-256.png"
+                            .to_string(),
+                        width:  192,
+                        height: 256,
+                    }),
+                    ..GameAssets::default()
+                },
+                moderators:     vec![
+                    GameModerator {
+                        user_id: 5,
+                        kind:    ModeratorKind::SuperModerator,
+                    },
+                    GameModerator {
+                        user_id: 15,
+                        kind:    ModeratorKind::Moderator,
+                    },
+                ],
+            }],
+            vec![Category {
+                game_id: 1,
+                slug:    "any".to_string(),
+                name:    "Any%".to_string(),
+                id:      2,
+                per:     CategoryType::PerLevel,
+                rules:   String::new(),
+            }],
+            vec![Level {
+                game_id: 1,
+                id:      3,
+                slug:    "level".to_string(),
+                name:    "Level".to_string(),
+                rules:   "Go fast.".to_string(),
+            }],
+            vec![Variable {
+                game_id:          1,
+                id:               6,
+                category_id:      Some(2),
+                scope:            VariableScope::SingleLevel(3),
+                slug:             "version".to_string(),
+                name:             "Version".to_string(),
+                is_subcategory:   true,
+                mandatory:        false,
+                obsoletes:        true,
+                default_value_id: Some(7),
+                values:           vec![
+                    VariableValue {
+                        id:            7,
+                        slug:          "1-0".to_string(),
+                        label:         "1.0".to_string(),
+                        rules:         String::new(),
+                        miscellaneous: false,
+                    },
+                    VariableValue {
+                        id:            8,
+                        slug:          "1-1".to_string(),
+                        label:         "1.1".to_string(),
+                        rules:         "Patched.".to_string(),
+                        miscellaneous: true,
+                    },
+                ],
+            }],
+            vec![Platform {
+                id:       9,
+                slug:     "nes".to_string(),
+                name:     "NES".to_string(),
+                released: 1983,
+            }],
+            vec![Region {
+                id:   10,
+                slug: "usa-ntsc".to_string(),
+                name: "USA / NTSC".to_string(),
+            }],
+            vec![
+                GameTag {
+                    id:   11,
+                    kind: GameTagKind::Genre,
+                    slug: "platformer".to_string(),
+                    name: "Platformer".to_string(),
+                },
+                GameTag {
+                    id:   12,
+                    kind: GameTagKind::Developer,
+                    slug: "nintendo".to_string(),
+                    name: "Nintendo".to_string(),
+                },
+            ],
+            vec![GameRelation {
+                game_id:      1,
+                base_game_id: None,
+                series_ids:   vec![14, 13],
+            }],
+            vec![UnverifiedRun {
+                run:    Run {
+                    game_id:     1,
+                    category_id: 2,
+                    level_id:    None,
+                    id:          16,
+                    created:     None,
+                    date:        None,
+                    times_ms:    RunTimesMs {
+                        igt:    None,
+                        rta:    None,
+                        rta_nl: Some(999),
+                    },
+                    players:     vec![RunPlayer::GuestName("cheater".to_string())],
+                    values:      vec![(6, 7)].into_iter().collect(),
+                    videos:      vec![],
+                    comment:     None,
+                    platform_id: None,
+                    region_id:   None,
+                    emulated:    false,
+                    examiner_id: Some(5),
+                    verified:    None,
+                },
+                status: ModerationStatus::Rejected,
+                reason: Some("No video.".to_string()),
+            }],
+        );
+
+        tables.write_sqlite(&path).expect("sqlite written");
+        let loaded = Tables::load_sqlite(&path).expect("sqlite loaded");
+
+        assert_eq!(tables.runs(), loaded.runs());
+        assert_eq!(tables.users(), loaded.users());
+        assert_eq!(tables.games(), loaded.games());
+        assert_eq!(tables.categories(), loaded.categories());
+        assert_eq!(tables.levels(), loaded.levels());
+        assert_eq!(tables.variables(), loaded.variables());
+        assert_eq!(tables.platforms(), loaded.platforms());
+        assert_eq!(tables.regions(), loaded.regions());
+        assert_eq!(tables.game_tags(), loaded.game_tags());
+        assert_eq!(tables.game_relations(), loaded.game_relations());
+        assert_eq!(tables.unverified_runs(), loaded.unverified_runs());
+    }
+}
@@ -0,0 +1,273 @@
+//! Sums of the best times on each individual level of a game, for a per-level
+//! category: the sum of the level records, its history, and each runner's
+//! personal sum of bests.
+use std::collections::{BTreeMap, HashMap};
+
+use getset::Getters;
+use serde::Serialize;
+
+use crate::data::{
+    database::Linked,
+    leaderboard::{leaderboard, performed_on, SystemFilter},
+    progression::progression,
+    types::*,
+};
+
+/// The best run on each of a game's levels, and the sum of their times.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[get = "pub"]
+pub struct SumOfBest {
+    /// The sum of the best times, over the levels that have a run.
+    sum_ms:         u64,
+    /// The best run on each level that has one, in level order.
+    level_bests:    Vec<LevelBest>,
+    /// The levels that don't have a run, so aren't counted in the sum.
+    missing_levels: Vec<Linked<Level>>,
+}
+
+#[derive(Debug, Clone, Getters, Serialize)]
+#[get = "pub"]
+pub struct LevelBest {
+    level:   Linked<Level>,
+    time_ms: u64,
+    run:     Linked<Run>,
+}
+
+/// A new level record, and what it did to the sum of level records.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[get = "pub"]
+pub struct SumOfBestProgress {
+    level:          Linked<Level>,
+    run:            Linked<Run>,
+    /// How much faster this run was than the previous level record, or 0 if
+    /// it was the first.
+    improvement_ms: u64,
+    /// The sum of the level records once this run was set, over the levels
+    /// that had a record by then.
+    sum_ms:         u64,
+    /// How many levels didn't have a record yet.
+    missing_levels: usize,
+}
+
+/// Sums the best times by the given `timing` method on each of the `levels`,
+/// from `runs` of a single per-level category. Only runs with every one of
+/// their level's variable `values` (value IDs by variable ID, by level ID) are
+/// counted.
+pub fn sum_of_best(
+    levels: &[Linked<Level>],
+    runs: &[Linked<Run>],
+    values: &BTreeMap<u64, BTreeMap<u64, u64>>,
+    timing: &TimingMethod,
+) -> SumOfBest {
+    let mut sum_ms = 0;
+    let mut level_bests = vec![];
+    let mut missing_levels = vec![];
+
+    for level in levels {
+        let level_runs = runs_on_level(runs, level);
+        let best = leaderboard(
+            &level_runs,
+            &level_values(values, level),
+            &SystemFilter::default(),
+            timing,
+            false,
+            None,
+        )
+        .into_iter()
+        .next();
+        match best {
+            Some(best) => {
+                sum_ms += *best.time_ms();
+                level_bests.push(LevelBest {
+                    level:   level.clone(),
+                    time_ms: *best.time_ms(),
+                    run:     best.run().clone(),
+                });
+            }
+            None => missing_levels.push(level.clone()),
+        }
+    }
+
+    SumOfBest {
+        sum_ms,
+        level_bests,
+        missing_levels,
+    }
+}
+
+/// Sums the given `player`'s best times on each of the `levels`, from `runs`
+/// of a single per-level category with the given variable `values`.
+pub fn personal_sum_of_best(
+    levels: &[Linked<Level>],
+    runs: &[Linked<Run>],
+    values: &BTreeMap<u64, BTreeMap<u64, u64>>,
+    timing: &TimingMethod,
+    player: &RunPlayer,
+) -> SumOfBest {
+    let runs: Vec<Linked<Run>> = runs
+        .iter()
+        .filter(|run| run.players().contains(player))
+        .cloned()
+        .collect();
+    sum_of_best(levels, &runs, values, timing)
+}
+
+/// Finds every new record on each of the `levels`, from `runs` of a single
+/// per-level category with the given variable `values`, in the order they
+/// were performed, with the sum of the level records after each.
+pub fn sum_of_best_progression(
+    levels: &[Linked<Level>],
+    runs: &[Linked<Run>],
+    values: &BTreeMap<u64, BTreeMap<u64, u64>>,
+    timing: &TimingMethod,
+) -> Vec<SumOfBestProgress> {
+    let mut records: Vec<(&Linked<Level>, Linked<Run>, u64)> = vec![];
+    for level in levels {
+        let level_runs = runs_on_level(runs, level);
+        for record in progression(&level_runs, &level_values(values, level), timing, false)
+        {
+            let time_ms = record
+                .run()
+                .times_ms()
+                .get(timing)
+                .expect("record has time");
+            records.push((level, record.run().clone(), time_ms));
+        }
+    }
+    records.sort_by_key(|(_, run, _)| (performed_on(run), *run.created(), *run.id()));
+
+    let mut record_ms_by_level_id: HashMap<u64, u64> = HashMap::new();
+    let mut sum_ms = 0;
+
+    records
+        .into_iter()
+        .map(|(level, run, time_ms)| {
+            let improvement_ms = match record_ms_by_level_id.insert(*level.id(), time_ms) {
+                Some(previous_ms) => {
+                    sum_ms -= previous_ms - time_ms;
+                    previous_ms - time_ms
+                }
+                None => {
+                    sum_ms += time_ms;
+                    0
+                }
+            };
+            SumOfBestProgress {
+                level: level.clone(),
+                run,
+                improvement_ms,
+                sum_ms,
+                missing_levels: levels.len() - record_ms_by_level_id.len(),
+            }
+        })
+        .collect()
+}
+
+fn runs_on_level(runs: &[Linked<Run>], level: &Level) -> Vec<Linked<Run>> {
+    runs.iter()
+        .filter(|run| *run.level_id() == Some(*level.id()))
+        .cloned()
+        .collect()
+}
+
+fn level_values(
+    values: &BTreeMap<u64, BTreeMap<u64, u64>>,
+    level: &Level,
+) -> BTreeMap<u64, u64> {
+    values.get(level.id()).cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::data::fixtures::{
+        self, category, game, level, tables, user, value, variable,
+    };
+
+    fn run(id: u64, user_id: u64, level_id: u64, rta: u64, month: u32) -> Run {
+        Run {
+            level_id: Some(level_id),
+            date: Some(NaiveDate::from_ymd(2019, month, 1)),
+            ..fixtures::run(id, user_id, rta)
+        }
+    }
+
+    #[test]
+    fn test_sum_of_best() {
+        let database = tables()
+            .runs(vec![
+                Run {
+                    values: vec![(7, 8)].into_iter().collect(),
+                    ..run(10, 5, 3, 100, 1)
+                },
+                run(11, 6, 4, 200, 2),
+                run(12, 6, 3, 80, 3),
+                run(13, 5, 4, 250, 4),
+            ])
+            .users((5..=6).map(user).collect())
+            .games(vec![game(1)])
+            .categories(vec![Category {
+                per: CategoryType::PerLevel,
+                ..category(2, 1)
+            }])
+            .levels((3..=5).map(|id| level(id, 1)).collect())
+            .variables(vec![Variable {
+                scope: VariableScope::SingleLevel(3),
+                ..variable(7, 2, vec![value(8, "Skip")])
+            }])
+            .database();
+        let game = database.game_by_id(1).expect("game exists");
+        let levels = game.levels();
+        let runs = game.runs();
+
+        let no_values = BTreeMap::new();
+        let record_sum = sum_of_best(&levels, &runs, &no_values, &TimingMethod::RTA);
+        assert_eq!(*record_sum.sum_ms(), 280);
+        assert_eq!(
+            record_sum
+                .level_bests()
+                .iter()
+                .map(|best| *best.run().id())
+                .collect::<Vec<_>>(),
+            vec![12, 11]
+        );
+        assert_eq!(
+            record_sum
+                .missing_levels()
+                .iter()
+                .map(|level| *level.id())
+                .collect::<Vec<_>>(),
+            vec![5]
+        );
+
+        let personal_sum = personal_sum_of_best(
+            &levels,
+            &runs,
+            &no_values,
+            &TimingMethod::RTA,
+            &RunPlayer::UserId(5),
+        );
+        assert_eq!(*personal_sum.sum_ms(), 350);
+
+        let level_3_values = vec![(3, vec![(7, 8)].into_iter().collect())]
+            .into_iter()
+            .collect();
+        let filtered_sum = sum_of_best(&levels, &runs, &level_3_values, &TimingMethod::RTA);
+        assert_eq!(*filtered_sum.sum_ms(), 300);
+
+        assert_eq!(
+            sum_of_best_progression(&levels, &runs, &no_values, &TimingMethod::RTA)
+                .iter()
+                .map(|progress| (
+                    *progress.run().id(),
+                    *progress.improvement_ms(),
+                    *progress.sum_ms(),
+                    *progress.missing_levels(),
+                ))
+                .collect::<Vec<_>>(),
+            vec![(10, 0, 100, 2), (11, 0, 300, 1), (12, 20, 280, 1)]
+        );
+    }
+}
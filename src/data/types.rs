@@ -1,9 +1,10 @@
 //! A simplified and normalized data model, with shared data referenced by ID.
 //!
 //! This doesn't include all of the metadata from speedrun.com, and excludes
-//! corrupt records and rejected or pending runs.
+//! corrupt records. Rejected and pending runs are kept apart from verified
+//! runs, as [UnverifiedRun]s.
 #![allow(missing_docs)]
-use std::convert::From;
+use std::{collections::BTreeMap, convert::From};
 
 use chrono::{DateTime, NaiveDate, Utc};
 use getset::Getters;
@@ -81,12 +82,17 @@ pub enum CategoryType {
 #[serde(deny_unknown_fields)]
 #[get = "pub"]
 pub struct User {
-    pub created: Option<DateTime<Utc>>,
+    pub created:    Option<DateTime<Utc>>,
     #[validate(length(min = 1))]
-    pub slug:    String,
+    pub slug:       String,
     #[validate(length(min = 1))]
-    pub name:    String,
-    pub id:      u64,
+    pub name:       String,
+    pub id:         u64,
+    pub location:   Option<UserLocation>,
+    pub links:      UserLinks,
+    pub role:       UserRole,
+    /// The colours of this user's name, or None if we don't know them.
+    pub name_style: Option<UserNameStyle>,
 }
 
 impl User {
@@ -99,6 +105,82 @@ impl User {
     pub fn src_slug(&self) -> String {
         src_slugify(self.name())
     }
+
+    /// Whether this user has been banned, so their runs should be hidden.
+    pub fn is_banned(&self) -> bool {
+        self.role == UserRole::Banned
+    }
+}
+
+/// Where a user says they're from.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq, Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct UserLocation {
+    /// The ISO 3166-1 alpha-2 country code, in lowercase, such as "ca".
+    pub country_code: Option<String>,
+    pub country:      String,
+    /// The country code followed by the region's code, such as "ca/on".
+    pub region_code:  Option<String>,
+    pub region:       Option<String>,
+}
+
+/// URLs of a user's profiles on other sites.
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct UserLinks {
+    pub twitch:        Option<String>,
+    pub youtube:       Option<String>,
+    pub twitter:       Option<String>,
+    pub hitbox:        Option<String>,
+    pub speedrunslive: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq)]
+#[serde(deny_unknown_fields)]
+pub enum UserRole {
+    User,
+    Trusted,
+    Moderator,
+    ContentModerator,
+    Admin,
+    Programmer,
+    Banned,
+}
+
+/// The colours speedrun.com displays a user's name in, each with a variant for
+/// dark and light backgrounds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq)]
+#[serde(deny_unknown_fields)]
+pub enum UserNameStyle {
+    Solid(UserColor),
+    Gradient { from: UserColor, to: UserColor },
+}
+
+/// A colour as a CSS hex string, such as "#EE4444".
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq, Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct UserColor {
+    pub dark:  String,
+    pub light: String,
 }
 
 #[derive(
@@ -126,6 +208,21 @@ pub struct Game {
     #[validate(length(min = 1))]
     pub name:           String,
     pub primary_timing: TimingMethod,
+    /// The platforms this game can be run on, if any are listed.
+    pub platform_ids:   Vec<u64>,
+    /// The regions this game was released in, if any are listed.
+    pub region_ids:     Vec<u64>,
+    /// The date this game was first released, if known.
+    pub release_date:   Option<NaiveDate>,
+    /// Whether this is a ROM hack of another game.
+    pub romhack:        bool,
+    /// This game's genres, developers, publishers, engines, and game types.
+    pub tag_ids:        Vec<u64>,
+    /// Cover art and other images of this game.
+    pub assets:         GameAssets,
+    /// The users who moderate this game's runs. These aren't validated, since
+    /// we don't keep every user.
+    pub moderators:     Vec<GameModerator>,
 }
 
 impl Game {
@@ -144,6 +241,187 @@ pub enum TimingMethod {
     RTA_NL,
 }
 
+/// A console or other system that games are run on, shared between games.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+    Validate,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct Platform {
+    pub id:       u64,
+    #[validate(length(min = 1))]
+    pub slug:     String,
+    #[validate(length(min = 1))]
+    pub name:     String,
+    /// The year this platform was released.
+    pub released: u32,
+}
+
+impl Platform {
+    /// This item's ID as it would be formatted for SpeedRun.Com.
+    pub fn src_id(&self) -> String {
+        base36(*self.id())
+    }
+}
+
+/// A release region, such as "USA / NTSC", shared between games.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+    Validate,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct Region {
+    pub id:   u64,
+    #[validate(length(min = 1))]
+    pub slug: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+impl Region {
+    /// This item's ID as it would be formatted for SpeedRun.Com.
+    pub fn src_id(&self) -> String {
+        base36(*self.id())
+    }
+}
+
+/// Images of a game hosted by speedrun.com, each of which may be missing.
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct GameAssets {
+    pub cover:      Option<GameAsset>,
+    pub icon:       Option<GameAsset>,
+    pub logo:       Option<GameAsset>,
+    pub background: Option<GameAsset>,
+}
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq, Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct GameAsset {
+    pub uri:    String,
+    pub width:  u32,
+    pub height: u32,
+}
+
+/// A genre, developer, publisher, engine, or game type, shared between games.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+    Validate,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct GameTag {
+    pub id:   u64,
+    pub kind: GameTagKind,
+    /// Unique among tags of the same kind.
+    #[validate(length(min = 1))]
+    pub slug: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+impl GameTag {
+    /// This item's ID as it would be formatted for SpeedRun.Com.
+    pub fn src_id(&self) -> String {
+        base36(*self.id())
+    }
+}
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Hash, PartialOrd, Ord, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub enum GameTagKind {
+    Genre,
+    Developer,
+    Publisher,
+    Engine,
+    GameType,
+}
+
+/// A user who moderates a game's runs.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq, Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct GameModerator {
+    pub user_id: u64,
+    pub kind:    ModeratorKind,
+}
+
+/// Super moderators can also add and remove a game's other moderators.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Hash, PartialOrd, Ord, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub enum ModeratorKind {
+    Moderator,
+    SuperModerator,
+}
+
+/// A game's relationships to other games, if it has any. These are kept apart
+/// from [Game] so that a dangling reference only loses the relationship.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq, Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct GameRelation {
+    pub game_id:      u64,
+    /// The game this is a category extension, ROM hack, or other derivative
+    /// of, if any.
+    pub base_game_id: Option<u64>,
+    /// The series this game belongs to. We don't have any other data about
+    /// series, so these only relate games to each other.
+    pub series_ids:   Vec<u64>,
+}
+
 #[derive(
     Debug,
     Serialize,
@@ -181,6 +459,111 @@ impl Level {
     }
 }
 
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+    Validate,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct Variable {
+    pub game_id:          u64,
+    pub id:               u64,
+    /// The only category this variable applies to, or None for all of them.
+    pub category_id:      Option<u64>,
+    pub scope:            VariableScope,
+    #[validate(length(min = 1))]
+    pub slug:             String,
+    #[validate(length(min = 1))]
+    pub name:             String,
+    /// Whether this variable splits leaderboards into subcategories, rather
+    /// than just annotating runs.
+    pub is_subcategory:   bool,
+    pub mandatory:        bool,
+    pub obsoletes:        bool,
+    pub default_value_id: Option<u64>,
+    #[validate]
+    pub values:           Vec<VariableValue>,
+}
+
+impl Variable {
+    /// This item's ID as it would be formatted for SpeedRun.Com.
+    pub fn src_id(&self) -> String {
+        base36(*self.id())
+    }
+
+    /// Finds one of this variable's values by ID.
+    pub fn value_by_id(&self, id: u64) -> Option<&VariableValue> {
+        self.values.iter().find(|value| value.id == id)
+    }
+
+    /// Finds one of this variable's values by slug.
+    pub fn value_by_slug(&self, slug: &str) -> Option<&VariableValue> {
+        self.values.iter().find(|value| value.slug == slug)
+    }
+
+    /// Returns whether this variable applies to runs of the given category and
+    /// level (None for full-game runs).
+    pub fn applies_to(&self, category_id: u64, level_id: Option<u64>) -> bool {
+        self.category_id.map_or(true, |id| id == category_id)
+            && match (&self.scope, level_id) {
+                (VariableScope::Global, _) => true,
+                (VariableScope::FullGame, level_id) => level_id.is_none(),
+                (VariableScope::AllLevels, level_id) => level_id.is_some(),
+                (VariableScope::SingleLevel(id), level_id) => level_id == Some(*id),
+            }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq)]
+#[serde(deny_unknown_fields)]
+pub enum VariableScope {
+    Global,
+    FullGame,
+    AllLevels,
+    SingleLevel(u64),
+}
+
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+    Validate,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct VariableValue {
+    pub id:            u64,
+    #[validate(length(min = 1))]
+    pub slug:          String,
+    #[validate(length(min = 1))]
+    pub label:         String,
+    pub rules:         String,
+    pub miscellaneous: bool,
+}
+
+impl VariableValue {
+    /// This item's ID as it would be formatted for SpeedRun.Com.
+    pub fn src_id(&self) -> String {
+        base36(*self.id())
+    }
+}
+
 #[derive(
     Debug,
     Serialize,
@@ -207,6 +590,19 @@ pub struct Run {
     pub times_ms:    RunTimesMs,
     #[validate]
     pub players:     Vec<RunPlayer>,
+    /// The ID of the value this run has for each variable, by variable ID.
+    pub values:      BTreeMap<u64, u64>,
+    pub videos:      Vec<RunVideo>,
+    pub comment:     Option<String>,
+    pub platform_id: Option<u64>,
+    pub region_id:   Option<u64>,
+    /// Whether this run was played on an emulator rather than real hardware.
+    pub emulated:    bool,
+    /// The user who verified this run, or rejected it if it's unverified.
+    /// This isn't validated, since we don't keep every user.
+    pub examiner_id: Option<u64>,
+    /// When this run was verified, if it was.
+    pub verified:    Option<DateTime<Utc>>,
 }
 
 impl Run {
@@ -216,6 +612,48 @@ impl Run {
     }
 }
 
+/// A run that hasn't been verified, either because it's still waiting in its
+/// game's moderation queue or because it was rejected. These are kept apart
+/// from verified runs so that leaderboards never include them.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Hash,
+    Clone,
+    PartialOrd,
+    Ord,
+    Eq,
+    Getters,
+    Validate,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct UnverifiedRun {
+    #[validate]
+    pub run:    Run,
+    pub status: ModerationStatus,
+    /// Why the run was rejected, if the moderator gave a reason.
+    pub reason: Option<String>,
+}
+
+impl UnverifiedRun {
+    /// This item's ID as it would be formatted for SpeedRun.Com.
+    pub fn src_id(&self) -> String {
+        self.run.src_id()
+    }
+}
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Hash, PartialOrd, Ord, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub enum ModerationStatus {
+    Pending,
+    Rejected,
+}
+
 #[derive(
     Debug, Serialize, Deserialize, PartialEq, Hash, Clone, PartialOrd, Ord, Eq, Getters,
 )]
@@ -248,6 +686,29 @@ impl Validate for RunTimesMs {
     }
 }
 
+/// A video of a run, with the details we could parse from its URL.
+#[derive(
+    Debug, Serialize, Deserialize, PartialEq, Hash, Clone, PartialOrd, Ord, Eq, Getters,
+)]
+#[serde(deny_unknown_fields)]
+#[get = "pub"]
+pub struct RunVideo {
+    pub url:      String,
+    pub platform: VideoPlatform,
+    /// The platform's ID for the video, if it's on a platform we recognize.
+    pub video_id: Option<String>,
+    /// How far into the video the run starts, if the URL specifies it.
+    pub start_s:  Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq)]
+#[serde(deny_unknown_fields)]
+pub enum VideoPlatform {
+    YouTube,
+    TwitchVod,
+    Other,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash, PartialOrd, Ord, Eq)]
 #[serde(deny_unknown_fields)]
 pub enum RunPlayer {